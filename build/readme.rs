@@ -0,0 +1,277 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Generates the `README.md` file from `docs.md`.
+//!
+//! This is included by `build.rs`, and by `tests/readme.rs` so that it can be tested.
+
+use std::fmt::{Display, Formatter};
+
+/// The copyright notice at the start of both `docs.md` and `README.md`.
+pub const COPYRIGHT: &str = "\
+<!-- This Source Code Form is subject to the terms of the Mozilla Public
+   - License, v. 2.0. If a copy of the MPL was not distributed with this
+   - file, You can obtain one at https://mozilla.org/MPL/2.0/. --> \
+";
+
+const NOTE: &str = "\
+<!-- This `README.md` file is automatically generated from `docs.md`, which uses `rustdoc`'s syntax
+   - to provide documentation for the `#[cfg_attrs { ... }]` macro too.
+   -
+   - See `build.rs` if you're interested to see the code, or edit `docs.md` to edit the
+   - documentation. --> \
+";
+
+const HEADER: &str = "# `#[cfg_attrs { ... }]`";
+
+/// A problem found in `docs.md` while generating the `README.md` file.
+pub struct Warning {
+	/// The (one-based) line number the problem was found at.
+	pub line: usize,
+	pub message: &'static str,
+}
+
+/// Generates the `README.md` file from the contents of `docs.md`, where indented code blocks are
+/// indented by `indentation_width` spaces.
+///
+/// Any problems found in `docs.md` are returned alongside it, for `build.rs` to report.
+pub fn generate(input: &str, indentation_width: usize) -> (String, Vec<Warning>) {
+	// `docs.md` may have `\r\n` line endings in a Windows checkout: the `README.md` is always
+	// generated with `\n` line endings, so that it doesn't depend on the platform.
+	let input = input.replace("\r\n", "\n");
+
+	let copyright_lines = COPYRIGHT.lines().count() + 1;
+
+	let doc = Doc::parse(input.lines().enumerate().skip(copyright_lines), indentation_width);
+
+	(doc.to_string(), doc.warnings)
+}
+
+struct Doc<'lines> {
+	nodes: Vec<Node<'lines>>,
+	warnings: Vec<Warning>,
+}
+
+impl<'lines> Doc<'lines> {
+	/// Parses the doc from its lines, each paired with its (zero-based) line index, where indented
+	/// code blocks are indented by `indentation_width` spaces.
+	fn parse(lines: impl IntoIterator<Item = (usize, &'lines str)>, indentation_width: usize) -> Self {
+		let mut code_block = None;
+		let mut nodes = Vec::new();
+		let mut warnings = Vec::new();
+
+		let code_indentation = |line: &str| {
+			let mut indentation = 0;
+
+			for r#char in line.chars().take(indentation_width) {
+				if r#char == ' ' {
+					indentation += 1;
+				} else {
+					break;
+				}
+			}
+
+			if indentation == indentation_width {
+				if let Some(r#char) = line.chars().nth(indentation_width) {
+					if r#char.is_whitespace() {
+						return None;
+					}
+				}
+			}
+
+			Some(indentation)
+		};
+
+		for (index, line) in lines {
+			if let Some(CodeBlock {
+				backticks,
+				indentation,
+				lines,
+				..
+			}) = &mut code_block
+			{
+				if code_indentation(line).is_some_and(|indent| indent < *indentation) && !line.is_empty() {
+					// End of the code block.
+
+					nodes.push(Node::CodeBlock(code_block.take().unwrap()));
+				} else {
+					if line.len() > *indentation && line[*indentation..].trim_end() == *backticks {
+						// End of the code block.
+
+						nodes.push(Node::CodeBlock(code_block.take().unwrap()));
+					} else {
+						lines.push(line.get(*indentation..).unwrap_or(""));
+					}
+
+					continue;
+				}
+			}
+
+			if let Some(indentation) = code_indentation(line) {
+				let mut backticks = 0;
+
+				for r#char in line.chars().skip(indentation) {
+					if r#char == '`' {
+						backticks += 1;
+					} else {
+						break;
+					}
+				}
+
+				if backticks >= 3 {
+					code_block = Some(CodeBlock {
+						line: index + 1,
+						backticks: &line[indentation..(indentation + backticks)],
+						indentation,
+
+						info: {
+							let info = line.get((indentation + backticks)..).map(|info| info.trim_end());
+
+							info.filter(|info| !info.is_empty())
+						},
+
+						lines: Vec::new(),
+					});
+
+					continue;
+				}
+			}
+
+			nodes.push(Node::Line(process_heading(line)));
+		}
+
+		if let Some(code_block) = code_block {
+			// The input ended before the code block was closed: close it anyway, rather than silently
+			// dropping it.
+			warnings.push(Warning {
+				line: code_block.line,
+				message: "unterminated code block, closing it at the end of the file",
+			});
+
+			nodes.push(Node::CodeBlock(code_block));
+		}
+
+		Doc { nodes, warnings }
+	}
+}
+
+enum Node<'lines> {
+	Line(String),
+	CodeBlock(CodeBlock<'lines>),
+}
+
+/// Represents a code block in the Markdown file.
+struct CodeBlock<'lines> {
+	/// The line number of the opening fence.
+	line: usize,
+	backticks: &'lines str,
+	indentation: usize,
+	info: Option<&'lines str>,
+	lines: Vec<&'lines str>,
+}
+
+/// Processes the `#`-hiding of lines within a Rust code block.
+///
+/// The `line` must already have had the code block's indentation removed.
+fn process_hiding(line: &str) -> Option<String> {
+	let trim = line.trim_end();
+
+	if trim == "#" || trim.starts_with("# ") {
+		// If it starts with `#`, it is hidden.
+		None
+	} else if trim == "##" || trim.starts_with("## ") {
+		// If it starts with `##`, then remove one of those `#`s.
+		Some(line[1..].to_owned())
+	} else {
+		// Otherwise, it's just a normal line.
+		Some(line.to_owned())
+	}
+}
+
+impl<'lines> CodeBlock<'lines> {
+	/// Returns whether the code block has lines, but all of them are hidden.
+	///
+	/// Such code blocks would be rendered empty, so they are omitted from the output entirely.
+	fn is_hidden(&self) -> bool {
+		!self.lines.is_empty() && self.lines.iter().all(|line| process_hiding(line).is_none())
+	}
+}
+
+/// Add an extra `#` to each heading.
+fn process_heading(line: &str) -> String {
+	const LEVELS: usize = 6;
+
+	let mut levels = 0;
+
+	for r#char in line.chars().take(LEVELS) {
+		if r#char == '#' {
+			levels += 1;
+		} else {
+			break;
+		}
+	}
+
+	if levels >= 1 && line.chars().nth(levels) == Some(' ') {
+		// Heading - insert an extra `#`.
+
+		format!("#{}", line)
+	} else {
+		// Not heading.
+
+		line.to_owned()
+	}
+}
+
+impl<'lines> Display for CodeBlock<'lines> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		let ws: String = " ".repeat(self.indentation);
+		let backticks = self.backticks;
+
+		writeln!(f, "{ws}{backticks}{}", self.info.unwrap_or("rust"))?;
+
+		// The lines are stored without the code block's indentation, so it is added back.
+		for line in &self.lines {
+			if let Some(line) = process_hiding(line) {
+				if line.is_empty() {
+					writeln!(f)?;
+				} else {
+					writeln!(f, "{ws}{line}")?;
+				}
+			}
+		}
+
+		write!(f, "{ws}{backticks}")?;
+
+		Ok(())
+	}
+}
+
+impl<'lines> Display for Node<'lines> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::CodeBlock(code_block) => write!(f, "{}", code_block),
+			Self::Line(line) => write!(f, "{}", line),
+		}
+	}
+}
+
+impl<'lines> Display for Doc<'lines> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "{}", COPYRIGHT)?;
+		writeln!(f)?;
+		writeln!(f, "{}", NOTE)?;
+		writeln!(f)?;
+		writeln!(f, "{}", HEADER)?;
+
+		for node in &self.nodes {
+			if matches!(node, Node::CodeBlock(code_block) if code_block.is_hidden()) {
+				continue;
+			}
+
+			writeln!(f, "{}", node)?;
+		}
+
+		Ok(())
+	}
+}
@@ -2,115 +2,769 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod shim;
+
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Debug, Display, Formatter};
+#[cfg(feature = "full")]
+use std::iter;
+#[cfg(feature = "full")]
+use std::mem;
+
 use proc_macro::TokenStream;
-use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream as TokenStream2, TokenTree};
 
-use quote::{quote, quote_spanned, ToTokens};
-use syn::parse::{Parse, ParseStream};
+use quote::{quote, quote_spanned, ToTokens, TokenStreamExt};
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream, Parser};
 use syn::punctuated::{Pair, Punctuated};
 use syn::spanned::Spanned;
+#[cfg(feature = "full")]
+use syn::{
+	parse_macro_input, Block, FnArg, Generics, ImplItem, Item, ItemStruct, Signature, Stmt, TraitItem, WherePredicate,
+};
 use syn::{
-	parse_macro_input, token, Attribute, Error, Field, Fields, FieldsNamed, Item, Meta, Path, Token, TraitItem,
-	WhereClause,
+	parse_quote, parse_quote_spanned, token, AttrStyle, Attribute, Error, Field, Fields, FieldsNamed, LitInt, LitStr,
+	Meta, Path, Token, Variant, WhereClause,
 };
+#[cfg(not(feature = "full"))]
+use syn::{Data, DeriveInput};
 
 #[doc = include_str!("../docs.md")]
 #[proc_macro_attribute]
 pub fn cfg_attrs(attr: TokenStream, item: TokenStream) -> TokenStream {
-	let cfg_attrs_error = if attr.is_empty() {
-		None
-	} else {
-		Some(Error::new(Span::call_site(), "unexpected token in attribute").into_compile_error())
+	// Nothing needs rewriting, so the item is emitted exactly as it was written, rather than being
+	// reconstructed.
+	if attr.is_empty() && !contains_helpers(item.clone().into()) {
+		return item;
+	}
+
+	let (mut options, cfg_attrs_error) = match syn::parse::<Options>(attr) {
+		Ok(options) => (options, TokenStream2::new()),
+		Err(error) => (Options::default(), error.into_compile_error()),
+	};
+
+	expand(&mut options, cfg_attrs_error, item.into()).into()
+}
+
+/// Expands `#[cfg_attrs]` on the given `item` with the given `options`, emitting the error from
+/// parsing the options, if any, as the `cfg_attrs_error`.
+fn expand(options: &mut Options, cfg_attrs_error: TokenStream2, item: TokenStream2) -> TokenStream2 {
+	#[cfg(feature = "metrics")]
+	let input_count = count_tokens(item.clone());
+
+	#[cfg(feature = "full")]
+	let item = match syn::parse2::<Item>(item) {
+		Ok(item) => item,
+		Err(error) => return options.error(error),
 	};
+	#[cfg(not(feature = "full"))]
+	let item = match syn::parse2::<DeriveInput>(item) {
+		Ok(input) => input,
+		Err(error) => {
+			let message = format!(
+				"{error} (without the `full` feature of `cfg_attrs`, only structs, enums, and unions are supported)"
+			);
+
+			return options.error(Error::new(error.span(), message));
+		},
+	};
+
+	#[cfg(feature = "full")]
+	let (is_trait, is_struct) = (matches!(item, Item::Trait(_)), matches!(item, Item::Struct(_)));
+	#[cfg(not(feature = "full"))]
+	let (is_trait, is_struct) = (false, matches!(item.data, Data::Struct(_)));
+
+	let rejected = reject_options(options, is_trait, is_struct);
+	let cfg_attrs_error = quote!(#cfg_attrs_error #rejected);
 
-	let item = to_tokens(parse_macro_input!(item as Item));
+	#[cfg(feature = "metrics")]
+	let name = item_ident(&item).map_or_else(|| "item".to_owned(), |ident| format!("`{ident}`"));
+
+	#[cfg(feature = "full")]
+	let item = to_tokens(item, options);
+	#[cfg(not(feature = "full"))]
+	let item = data_to_tokens(item, options);
+	let warnings = options.warnings.take();
 
 	let tokens = quote! {
 		#cfg_attrs_error
+		#warnings
 		#item
 	};
 
+	#[cfg(feature = "self_check")]
+	let tokens = self_check(tokens);
+
+	#[cfg(feature = "metrics")]
+	eprintln!(
+		"cfg_attrs: expanded {name} from {input_count} tokens to {} tokens",
+		count_tokens(tokens.clone()),
+	);
+
+	tokens
+}
+
+/// Like [`expand`], but returns the first error found instead of emitting it, so that error cases
+/// can be unit tested without looking for `compile_error!` invocations in the output.
+///
+/// This can't be exported, since this is a proc-macro crate.
+#[cfg(test)]
+fn try_expand(attr: TokenStream2, item: TokenStream2) -> syn::Result<TokenStream2> {
+	let mut options: Options = syn::parse2(attr)?;
+	let tokens = expand(&mut options, TokenStream2::new(), item);
+
+	match options.first_error.take() {
+		Some(error) => Err(error),
+		None => Ok(tokens),
+	}
+}
+
+/// Rejects the `apply_to_members` and `apply_to_fields` options if the item `#[cfg_attrs]` is on isn't
+/// a trait or a struct respectively.
+///
+/// Rejected options are removed, so that they aren't applied to the traits and structs nested within
+/// the item either.
+#[cfg_attr(not(feature = "full"), allow(unused_variables))]
+fn reject_options(options: &mut Options, is_trait: bool, is_struct: bool) -> TokenStream2 {
+	let mut errors = TokenStream2::new();
+
+	#[cfg(feature = "full")]
+	if let (false, Some(attribute)) = (is_trait, options.apply_to_members.first()) {
+		let message = "`apply_to_members` can only be used on traits";
+		errors.extend(options.error(Error::new(attribute.path().span(), message)));
+
+		options.apply_to_members.clear();
+	}
+
+	if let (false, Some((attribute, _))) = (is_struct, options.apply_to_fields.first()) {
+		let message = "`apply_to_fields` can only be used on structs";
+		errors.extend(options.error(Error::new(attribute.path().span(), message)));
+
+		options.apply_to_fields.clear();
+	}
+
+	errors
+}
+
+/// Processes each of the given items as though it were marked with `#[cfg_attrs]`.
+///
+/// This is for items which can't be marked with `#[cfg_attrs]` themselves, such as those generated
+/// by a macro. See [`#[cfg_attrs]`](macro@cfg_attrs) for the helper attributes that can be used.
+///
+/// ```
+/// # use cfg_attrs::cfg_attrs_items;
+/// #
+/// macro_rules! markers {
+///     ($($name:ident),*) => {
+///         cfg_attrs_items! {
+///             $(
+///                 #[configure(feature = "debug", #[derive(Debug)])]
+///                 struct $name;
+///             )*
+///         }
+///     };
+/// }
+///
+/// markers!(First, Second);
+/// ```
+#[cfg(feature = "full")]
+#[proc_macro]
+pub fn cfg_attrs_items(input: TokenStream) -> TokenStream {
+	if !contains_helpers(input.clone().into()) {
+		return input;
+	}
+
+	#[cfg(feature = "metrics")]
+	let input_count = count_tokens(input.clone().into());
+
+	let items = parse_macro_input!(input with parse_items);
+
+	// Each item gets its own options, so that its warnings can be emitted with it.
+	let tokens: TokenStream2 = items
+		.into_iter()
+		.map(|item| {
+			let options = Options::default();
+
+			let item = to_tokens(item, &options);
+			let warnings = options.warnings.into_inner();
+
+			quote!(#warnings #item)
+		})
+		.collect();
+
+	#[cfg(feature = "self_check")]
+	let tokens = self_check(tokens);
+
+	#[cfg(feature = "metrics")]
+	eprintln!(
+		"cfg_attrs: expanded items from {input_count} tokens to {} tokens",
+		count_tokens(tokens.clone()),
+	);
+
 	tokens.into()
 }
 
+/// Parses any number of items, up until the end of the `input`.
+#[cfg(feature = "full")]
+fn parse_items(input: ParseStream) -> syn::Result<Vec<Item>> {
+	let mut items = Vec::new();
+
+	while !input.is_empty() {
+		items.push(input.parse()?);
+	}
+
+	Ok(items)
+}
+
+/// Checks that the given `tokens` generated by an expansion parse as items, replacing them with an
+/// error if they don't, so that a bug in the rewriting is reported as such.
+#[cfg(feature = "self_check")]
+fn self_check(tokens: TokenStream2) -> TokenStream2 {
+	match parse_items.parse2(tokens.clone()) {
+		Ok(_) => tokens,
+
+		Err(error) => {
+			let message = format!(
+				"`cfg_attrs` generated invalid code, which is a bug in `cfg_attrs`: {error}\n\nthe generated code \
+				 was: {tokens}"
+			);
+
+			Error::new(error.span(), message).into_compile_error()
+		},
+	}
+}
+
+/// Counts the number of token trees in the given `tokens`, including those nested within groups.
+#[cfg(feature = "metrics")]
+fn count_tokens(tokens: TokenStream2) -> usize {
+	tokens
+		.into_iter()
+		.map(|tree| match tree {
+			TokenTree::Group(group) => 1 + count_tokens(group.stream()),
+			_ => 1,
+		})
+		.sum()
+}
+
+/// Returns the name of the given `item`.
+#[cfg(all(feature = "metrics", not(feature = "full")))]
+fn item_ident(item: &DeriveInput) -> Option<&Ident> {
+	Some(&item.ident)
+}
+
+/// Returns the name of the given `item`, if it has one.
+#[cfg(all(feature = "metrics", feature = "full"))]
+fn item_ident(item: &Item) -> Option<&Ident> {
+	match item {
+		Item::Const(r#const) => Some(&r#const.ident),
+		Item::Enum(r#enum) => Some(&r#enum.ident),
+		Item::ExternCrate(r#extern) => Some(&r#extern.ident),
+		Item::Fn(r#fn) => Some(&r#fn.sig.ident),
+		Item::Macro(r#macro) => r#macro.ident.as_ref(),
+		Item::Mod(r#mod) => Some(&r#mod.ident),
+		Item::Static(r#static) => Some(&r#static.ident),
+		Item::Struct(r#struct) => Some(&r#struct.ident),
+		Item::Trait(r#trait) => Some(&r#trait.ident),
+		Item::TraitAlias(alias) => Some(&alias.ident),
+		Item::Type(r#type) => Some(&r#type.ident),
+		Item::Union(union) => Some(&union.ident),
+
+		_ => None,
+	}
+}
+
+/// The `#[configure(...)]` helper attribute, which is only valid on items with `#[cfg_attrs]`.
+///
+/// This exists so that `#[configure(...)]` attributes which aren't processed by `#[cfg_attrs]`,
+/// usually because they were placed before it, result in a helpful error. See
+/// [`#[cfg_attrs]`](macro@cfg_attrs) for how to use `#[configure(...)]`.
+#[proc_macro_attribute]
+pub fn configure(_attr: TokenStream, item: TokenStream) -> TokenStream {
+	let error = Error::new(
+		Span::call_site(),
+		"`#[configure(...)]` must be placed after `#[cfg_attrs]`, which processes it: `#[cfg_attrs] \
+		 #[configure(...)]`",
+	)
+	.into_compile_error();
+
+	// The item is still emitted, so that it doesn't cause other errors by being missing.
+	let item = TokenStream2::from(item);
+
+	quote! {
+		#error
+		#item
+	}
+	.into()
+}
+
+/// Returns whether the given `tokens` contain any of the [`HELPERS`] as an outer attribute.
+///
+/// This only looks at the tokens, so helpers within function bodies and macro invocations are also
+/// found, even though they aren't rewritten.
+fn contains_helpers(tokens: TokenStream2) -> bool {
+	let mut tokens = tokens.into_iter().peekable();
+
+	while let Some(tree) = tokens.next() {
+		let found = match tree {
+			TokenTree::Punct(punct) if punct.as_char() == '#' => {
+				matches!(tokens.peek(), Some(TokenTree::Group(group)) if is_helper_attribute(group))
+			},
+
+			TokenTree::Group(group) => contains_helpers(group.stream()),
+			_ => false,
+		};
+
+		if found {
+			return true;
+		}
+	}
+
+	false
+}
+
+/// Returns whether the given `group` is the bracketed part of one of the [`HELPERS`], which may be
+/// qualified as `cfg_attrs::helper`.
+fn is_helper_attribute(group: &Group) -> bool {
+	if group.delimiter() != Delimiter::Bracket {
+		return false;
+	}
+
+	let mut tokens = group.stream().into_iter();
+	let mut first = tokens.next();
+
+	if matches!(&first, Some(TokenTree::Ident(ident)) if ident == "cfg_attrs") {
+		let colons = (tokens.next(), tokens.next());
+		if !matches!(colons, (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b))) if a.as_char() == ':' && b.as_char() == ':')
+		{
+			return false;
+		}
+
+		first = tokens.next();
+	}
+
+	matches!(first, Some(TokenTree::Ident(ident)) if HELPERS.iter().any(|helper| ident == helper))
+}
+
+/// Returns whether the given `path` refers to the helper attribute `name`, either bare or qualified
+/// as `cfg_attrs::name`.
+fn is_helper_path(path: &Path, name: &str) -> bool {
+	if path.is_ident(name) {
+		return true;
+	}
+
+	let segments: Vec<_> = path.segments.iter().collect();
+
+	matches!(
+		segments[..],
+		[krate, helper] if krate.ident == "cfg_attrs"
+			&& helper.ident == name
+			&& krate.arguments.is_none()
+			&& helper.arguments.is_none()
+	)
+}
+
+/// The names of the helper attributes recognised by `#[cfg_attrs]`.
+const HELPERS: &[&str] = &[
+	"cfg_attrs_skip",
+	"configure",
+	"configure_body",
+	"configure_cfg",
+	"configure_cfg_if",
+	"configure_each",
+	"configure_field",
+	"configure_if",
+	"configure_where",
+];
+
+/// The helper attributes which rewrite or duplicate the whole item they are placed on, rather than
+/// being rewritten to `cfg_attr`. These require the `full` feature.
+const ITEM_HELPERS: &[&str] = &[
+	"configure_body",
+	"configure_cfg",
+	"configure_cfg_if",
+	"configure_field",
+	"configure_where",
+];
+
+/// Options given to the `#[cfg_attrs(...)]` attribute itself.
+struct Options {
+	/// The maximum depth to which `configure` attributes may be nested.
+	recursion_limit: usize,
+	/// Whether configured attributes that duplicate unconditional attributes are warned about.
+	warn_duplicates: bool,
+	/// Whether attributes that look like misspelled helper attributes are errors.
+	deny_unknown: bool,
+	/// Whether `configure` attributes (and the other helpers) are removed, rather than rewritten.
+	strip: bool,
+	/// Whether item-level `configure` attributes which only configure `cfg` attributes duplicate the
+	/// item, like `configure_cfg`, rather than being rewritten to `cfg_attr`.
+	#[cfg(feature = "full")]
+	split_cfg: bool,
+	/// Whether rewritten attributes are emitted with mixed-site hygiene, rather than that of the
+	/// tokens they come from.
+	mixed_site: bool,
+	/// Whether configured attributes are sorted, so that the output doesn't depend on the order in
+	/// which they were written.
+	sort: bool,
+	/// Whether bare identifiers in conditions are treated as features.
+	features_only: bool,
+	/// `#[configure(...)]` attributes added to each of a trait's items.
+	#[cfg(feature = "full")]
+	apply_to_members: Vec<Attribute>,
+	/// `#[configure(...)]` attributes added to each of a struct's fields, along with the paths of the
+	/// attributes they configure.
+	apply_to_fields: Vec<(Attribute, Vec<String>)>,
+
+	/// Warnings and audit markers emitted during the expansion, which are emitted before the item
+	/// they are for, or at the start of its body or value if it is a function or constant.
+	warnings: RefCell<TokenStream2>,
+	/// Errors which can't be emitted in place of the attribute that caused them, such as those on
+	/// fields, which are emitted before the affected item.
+	errors: RefCell<TokenStream2>,
+	/// Whether the error being returned from nested attributes already has its note, or shouldn't be
+	/// given one, so that the attributes it is nested within don't add theirs. Errors pass through
+	/// `syn`'s parsers on their way out, so this can't be carried by the error itself.
+	noted: Cell<bool>,
+	/// The first error emitted during the expansion, for [`try_expand`].
+	#[cfg(test)]
+	first_error: RefCell<Option<Error>>,
+	/// Whether the item being processed is nested within the item `#[cfg_attrs]` is on.
+	#[cfg(feature = "full")]
+	nested: Cell<bool>,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			recursion_limit: 128,
+			warn_duplicates: false,
+			deny_unknown: false,
+			strip: false,
+			#[cfg(feature = "full")]
+			split_cfg: false,
+			mixed_site: false,
+			sort: false,
+			features_only: false,
+			#[cfg(feature = "full")]
+			apply_to_members: Vec::new(),
+			apply_to_fields: Vec::new(),
+
+			warnings: RefCell::default(),
+			errors: RefCell::default(),
+			noted: Cell::new(false),
+			#[cfg(test)]
+			first_error: RefCell::default(),
+			#[cfg(feature = "full")]
+			nested: Cell::new(false),
+		}
+	}
+}
+
+impl Options {
+	/// Emits a warning with the given `message` at the given `span`.
+	///
+	/// Proc macros can't emit warnings on stable, so this makes use of a deprecated item.
+	fn warn(&self, span: Span, message: &str) {
+		let ident = Ident::new("cfg_attrs_warning", span);
+
+		self.warnings.borrow_mut().extend(quote! {
+			const _: () = {
+				#[deprecated(note = #message)]
+				#[allow(non_camel_case_types)]
+				struct cfg_attrs_warning;
+
+				let _ = #ident;
+			};
+		});
+	}
+
+	/// Converts the given `error` to a `compile_error!` invocation, to be emitted in place of what
+	/// caused it.
+	fn error(&self, error: Error) -> TokenStream2 {
+		#[cfg(test)]
+		self.first_error.borrow_mut().get_or_insert_with(|| error.clone());
+
+		error.into_compile_error()
+	}
+
+	/// Emits an audit marker recording that the `original` attribute was rewritten to `rewritten`.
+	#[cfg(feature = "audit")]
+	fn mark(&self, original: &Attribute, rewritten: &Attr) {
+		let original = original.to_token_stream().to_string();
+		let rewritten = rewritten.to_token_stream().to_string();
+
+		self.warnings.borrow_mut().extend(quote! {
+			#[doc(hidden)]
+			const _: (&str, &str) = (#original, #rewritten);
+		});
+	}
+
+	/// Returns the warnings (and audit markers) emitted by `f`, leaving those emitted before it to be
+	/// emitted as they would have been.
+	#[cfg(feature = "full")]
+	fn warnings_of(&self, f: impl FnOnce()) -> TokenStream2 {
+		let outer = self.warnings.take();
+		f();
+
+		self.warnings.replace(outer)
+	}
+
+	/// Returns the warnings emitted by `f` for a function or constant, to be emitted within its body
+	/// or value.
+	///
+	/// Warnings usually go before the item they are for, but that isn't valid if `#[cfg_attrs]` is
+	/// on an associated function or constant, which can't be told apart from a free one. Nested
+	/// items can't be associated items, so their warnings are left to go before them.
+	#[cfg(feature = "full")]
+	fn item_warnings(&self, f: impl FnOnce()) -> TokenStream2 {
+		if self.nested.get() {
+			f();
+			return TokenStream2::new();
+		}
+
+		self.warnings_of(f)
+	}
+
+	/// Processes nested items with `f`.
+	#[cfg(feature = "full")]
+	fn nested(&self, f: impl FnOnce()) {
+		let nested = self.nested.replace(true);
+		f();
+		self.nested.set(nested);
+	}
+}
+
 enum Attr {
 	Configure {
 		hash: Token![#],
+		style: AttrStyle,
 		square_bracket: token::Bracket,
 		path: Path,
 		meta: ConfigureMeta,
 	},
 
+	/// A `configure_if` attribute, with its arms' conditions already accumulated so that only the
+	/// first matching arm applies, or a `configure_each` attribute, each of whose rows is an arm
+	/// that applies independently.
+	Arms {
+		hash: Token![#],
+		style: AttrStyle,
+		square_bracket: token::Bracket,
+		path: Path,
+		arms: Vec<ConfigureMeta>,
+	},
+
 	Other(Attribute),
 }
 
 struct ConfigureMeta {
-	condition: Meta,
+	condition: Condition,
 	comma: Token![,],
-	attrs: Punctuated<Attr, Token![,]>,
+	attrs: Vec<Attr>,
+	/// Attributes to configure if the condition *doesn't* hold.
+	otherwise: Option<(Token![else], Vec<Attr>)>,
+}
+
+/// A `configure` condition, which may be negated with a leading `!`.
+struct Condition {
+	not: Option<Token![!]>,
+	meta: Meta,
+}
+
+/// Rewrites the given struct, enum, or union, for when the `full` feature is disabled.
+///
+/// Only attributes are rewritten: the helper attributes that duplicate or extend the item require
+/// the `full` feature.
+#[cfg(not(feature = "full"))]
+fn data_to_tokens(mut input: DeriveInput, options: &Options) -> TokenStream2 {
+	let len = input.attrs.len();
+	input
+		.attrs
+		.retain(|attribute| !is_helper_path(attribute.path(), "cfg_attrs_skip"));
+
+	if input.attrs.len() != len {
+		// The item has opted out of processing: emit it verbatim, minus the `#[cfg_attrs_skip]`
+		// marker itself.
+		return input.into_token_stream();
+	}
+
+	let mut tokens = TokenStream2::new();
+
+	input.attrs.retain(|attribute| {
+		let helper = ITEM_HELPERS
+			.iter()
+			.find(|helper| is_helper_path(attribute.path(), helper));
+
+		if let Some(helper) = helper {
+			let message = format!("`{helper}` requires the `full` feature of `cfg_attrs`");
+			tokens.extend(options.error(Error::new(attribute.path().span(), message)));
+		}
+
+		helper.is_none()
+	});
+
+	attrs_to_tokens(input.attrs, options, &mut tokens);
+
+	let (generics, where_clause) = shim::split_generics(&input.generics);
+
+	input.vis.to_tokens(&mut tokens);
+
+	match input.data {
+		Data::Struct(mut data) => {
+			apply_to_fields(&mut data.fields, options);
+
+			data.struct_token.to_tokens(&mut tokens);
+			input.ident.to_tokens(&mut tokens);
+
+			generics.to_tokens(&mut tokens);
+
+			fields_to_tokens(data.fields, where_clause, options, &mut tokens);
+			data.semi_token.to_tokens(&mut tokens);
+		},
+
+		Data::Enum(data) => {
+			data.enum_token.to_tokens(&mut tokens);
+			input.ident.to_tokens(&mut tokens);
+
+			generics.to_tokens(&mut tokens);
+			where_clause.to_tokens(&mut tokens);
+
+			data.brace_token.surround(&mut tokens, |tokens| {
+				variants_to_tokens(data.variants, options, tokens);
+			});
+		},
+
+		Data::Union(data) => {
+			data.union_token.to_tokens(&mut tokens);
+			input.ident.to_tokens(&mut tokens);
+
+			generics.to_tokens(&mut tokens);
+			where_clause.to_tokens(&mut tokens);
+
+			fields_named_to_tokens(data.fields, options, &mut tokens);
+		},
+	}
+
+	// Errors that couldn't be emitted within the item, such as those for fields, go before it.
+	let mut errors = options.errors.take();
+	errors.extend(tokens);
+
+	errors
 }
 
-fn to_tokens(item: Item) -> TokenStream2 {
+#[cfg(feature = "full")]
+fn to_tokens(mut item: Item, options: &Options) -> TokenStream2 {
+	if let Some(attrs) = item_attrs(&mut item) {
+		let len = attrs.len();
+		attrs.retain(|attribute| !is_helper_path(attribute.path(), "cfg_attrs_skip"));
+
+		if attrs.len() != len {
+			// The item has opted out of processing: emit it verbatim, minus the `#[cfg_attrs_skip]`
+			// marker itself.
+			return item.into_token_stream();
+		}
+
+		if options.strip {
+			strip_item_helpers(attrs);
+		}
+
+		if let Some(index) = attrs
+			.iter()
+			.position(|attribute| is_helper_path(attribute.path(), "configure_cfg"))
+		{
+			let attribute = attrs.remove(index);
+
+			return configure_cfg_to_tokens(item, index, attribute, options);
+		}
+
+		if let Some(index) = attrs
+			.iter()
+			.position(|attribute| is_helper_path(attribute.path(), "configure_cfg_if"))
+		{
+			let attribute = attrs.remove(index);
+
+			return configure_cfg_if_to_tokens(item, index, attribute, options);
+		}
+
+		if options.split_cfg {
+			if let Some(index) = attrs
+				.iter()
+				.position(|attribute| configures_only_cfg(attribute, options))
+			{
+				let attribute = attrs.remove(index);
+
+				return configure_cfg_to_tokens(item, index, attribute, options);
+			}
+		}
+
+		if let Some(index) = attrs
+			.iter()
+			.position(|attribute| is_helper_path(attribute.path(), "configure_body"))
+		{
+			let attribute = attrs.remove(index);
+
+			return configure_body_to_tokens(item, index, attribute, options);
+		}
+
+		if attrs
+			.iter()
+			.any(|attribute| is_helper_path(attribute.path(), "configure_where"))
+		{
+			let mut tokens = TokenStream2::new();
+
+			for item in split_where(item, item_parts, options, &mut tokens) {
+				tokens.extend(to_tokens(item, options));
+			}
+
+			return tokens;
+		}
+	}
+
 	let mut tokens = TokenStream2::new();
 
 	match item {
 		Item::Const(r#const) => {
-			attrs_to_tokens(r#const.attrs, &mut tokens);
-
-			let (impl_generics, _, where_clause) = r#const.generics.split_for_impl();
+			let warnings = options.item_warnings(|| attrs_to_tokens(r#const.attrs, options, &mut tokens));
 
+			// Generic constants are unstable, and `syn` doesn't parse their generics, so there is
+			// neither a parameter list nor a where clause to emit.
 			r#const.vis.to_tokens(&mut tokens);
 			r#const.const_token.to_tokens(&mut tokens);
 			r#const.ident.to_tokens(&mut tokens);
-
-			impl_generics.to_tokens(&mut tokens);
-
 			r#const.colon_token.to_tokens(&mut tokens);
 			r#const.ty.to_tokens(&mut tokens);
 			r#const.eq_token.to_tokens(&mut tokens);
-			r#const.expr.to_tokens(&mut tokens);
 
-			where_clause.to_tokens(&mut tokens);
+			let expr = r#const.expr;
+			if warnings.is_empty() {
+				expr.to_tokens(&mut tokens);
+			} else {
+				tokens.extend(quote!({ #warnings #expr }));
+			}
 
 			r#const.semi_token.to_tokens(&mut tokens);
 		},
 
 		Item::Enum(r#enum) => {
-			attrs_to_tokens(r#enum.attrs, &mut tokens);
+			attrs_to_tokens(r#enum.attrs, options, &mut tokens);
 
-			let (impl_generics, _, where_clause) = r#enum.generics.split_for_impl();
+			let (generics, where_clause) = shim::split_generics(&r#enum.generics);
 
 			r#enum.vis.to_tokens(&mut tokens);
 			r#enum.enum_token.to_tokens(&mut tokens);
 			r#enum.ident.to_tokens(&mut tokens);
 
-			impl_generics.to_tokens(&mut tokens);
+			generics.to_tokens(&mut tokens);
 			where_clause.to_tokens(&mut tokens);
 
 			r#enum.brace_token.surround(&mut tokens, |tokens| {
-				for pair in r#enum.variants.into_pairs() {
-					let (variant, comma) = match pair {
-						Pair::Punctuated(variant, comma) => (variant, Some(comma)),
-						Pair::End(variant) => (variant, None),
-					};
-
-					attrs_to_tokens(variant.attrs, tokens);
-
-					variant.ident.to_tokens(tokens);
-					if let Some((eq, discrim)) = &variant.discriminant {
-						eq.to_tokens(tokens);
-						discrim.to_tokens(tokens);
-					};
-
-					fields_to_tokens(variant.fields, None, tokens);
-
-					comma.to_tokens(tokens);
-				}
+				variants_to_tokens(r#enum.variants, options, tokens);
 			});
 		},
 
 		Item::ExternCrate(r#extern) => {
-			attrs_to_tokens(r#extern.attrs, &mut tokens);
+			attrs_to_tokens(r#extern.attrs, options, &mut tokens);
 
 			r#extern.vis.to_tokens(&mut tokens);
 			r#extern.extern_token.to_tokens(&mut tokens);
@@ -124,23 +778,149 @@ fn to_tokens(item: Item) -> TokenStream2 {
 		},
 
 		Item::Fn(r#fn) => {
-			attrs_to_tokens(r#fn.attrs, &mut tokens);
+			let (outer, inner) = split_inner(r#fn.attrs);
+			let warnings = options.item_warnings(|| {
+				attrs_to_tokens(outer, options, &mut tokens);
+
+				r#fn.vis.to_tokens(&mut tokens);
+				sig_to_tokens(r#fn.sig, options, &mut tokens);
+			});
+
+			block_to_tokens(*r#fn.block, inner, warnings, options, &mut tokens);
+		},
+
+		Item::Impl(r#impl) => {
+			let (outer, inner) = split_inner(r#impl.attrs);
+			attrs_to_tokens(outer, options, &mut tokens);
+
+			let (generics, where_clause) = shim::split_generics(&r#impl.generics);
+
+			r#impl.defaultness.to_tokens(&mut tokens);
+			r#impl.unsafety.to_tokens(&mut tokens);
+			r#impl.impl_token.to_tokens(&mut tokens);
+
+			generics.to_tokens(&mut tokens);
+
+			if let Some((not, path, r#for)) = &r#impl.trait_ {
+				not.to_tokens(&mut tokens);
+				path.to_tokens(&mut tokens);
+				r#for.to_tokens(&mut tokens);
+			}
+			r#impl.self_ty.to_tokens(&mut tokens);
+
+			where_clause.to_tokens(&mut tokens);
+
+			r#impl.brace_token.surround(&mut tokens, |tokens| {
+				attrs_to_tokens(inner, options, tokens);
+
+				let items = r#impl
+					.items
+					.into_iter()
+					.map(|mut item| {
+						if let Some((attrs, _)) = impl_item_parts(&mut item) {
+							if options.strip {
+								strip_item_helpers(attrs);
+							}
+						}
+
+						item
+					})
+					.flat_map(|item| split_members(item, impl_item_parts, impl_item_body, options, tokens));
+
+				for item in items.collect::<Vec<_>>() {
+					match item {
+						ImplItem::Const(r#const) => {
+							attrs_to_tokens(r#const.attrs, options, tokens);
+
+							r#const.vis.to_tokens(tokens);
+							r#const.defaultness.to_tokens(tokens);
+							r#const.const_token.to_tokens(tokens);
+							r#const.ident.to_tokens(tokens);
+							r#const.colon_token.to_tokens(tokens);
+							r#const.ty.to_tokens(tokens);
+							r#const.eq_token.to_tokens(tokens);
+							r#const.expr.to_tokens(tokens);
+							r#const.semi_token.to_tokens(tokens);
+						},
+
+						ImplItem::Fn(r#fn) => {
+							let (outer, inner) = split_inner(r#fn.attrs);
+							attrs_to_tokens(outer, options, tokens);
+
+							r#fn.vis.to_tokens(tokens);
+							r#fn.defaultness.to_tokens(tokens);
+							sig_to_tokens(r#fn.sig, options, tokens);
+							block_to_tokens(r#fn.block, inner, TokenStream2::new(), options, tokens);
+						},
+
+						ImplItem::Macro(r#macro) => {
+							attrs_to_tokens(r#macro.attrs, options, tokens);
+
+							r#macro.mac.to_tokens(tokens);
+							r#macro.semi_token.to_tokens(tokens);
+						},
+
+						ImplItem::Type(r#type) => {
+							attrs_to_tokens(r#type.attrs, options, tokens);
+
+							let (generics, where_clause) = shim::split_generics(&r#type.generics);
+
+							r#type.vis.to_tokens(tokens);
+							r#type.defaultness.to_tokens(tokens);
+							r#type.type_token.to_tokens(tokens);
+							r#type.ident.to_tokens(tokens);
+
+							generics.to_tokens(tokens);
+
+							r#type.eq_token.to_tokens(tokens);
+							r#type.ty.to_tokens(tokens);
+
+							where_clause.to_tokens(tokens);
+
+							r#type.semi_token.to_tokens(tokens);
+						},
+
+						other => other.to_tokens(tokens),
+					}
+				}
+			});
+		},
 
-			r#fn.vis.to_tokens(&mut tokens);
-			r#fn.sig.to_tokens(&mut tokens);
-			r#fn.block.to_tokens(&mut tokens);
+		Item::Macro(mut r#macro) => {
+			attrs_to_tokens(mem::take(&mut r#macro.attrs), options, &mut tokens);
+
+			// The name of a `macro_rules!` definition goes between the `!` and the rules, so the rest of
+			// the item is emitted as a whole.
+			r#macro.to_tokens(&mut tokens);
 		},
 
-		Item::Macro(r#macro) => {
-			attrs_to_tokens(r#macro.attrs, &mut tokens);
+		Item::Mod(r#mod) => {
+			let (outer, inner) = split_inner(r#mod.attrs);
+			attrs_to_tokens(outer, options, &mut tokens);
+
+			r#mod.vis.to_tokens(&mut tokens);
+			r#mod.unsafety.to_tokens(&mut tokens);
+			r#mod.mod_token.to_tokens(&mut tokens);
+			r#mod.ident.to_tokens(&mut tokens);
+
+			match r#mod.content {
+				Some((brace, items)) => brace.surround(&mut tokens, |tokens| {
+					attrs_to_tokens(inner, options, tokens);
+
+					// The module's items are processed independently of the module itself.
+					options.nested(|| {
+						for item in items {
+							tokens.extend(to_tokens(item, options));
+						}
+					});
+				}),
 
-			r#macro.ident.to_tokens(&mut tokens);
-			r#macro.mac.to_tokens(&mut tokens);
-			r#macro.semi_token.to_tokens(&mut tokens);
+				None => r#mod.semi.to_tokens(&mut tokens),
+			}
 		},
 
 		Item::Static(r#static) => {
-			attrs_to_tokens(r#static.attrs, &mut tokens);
+			attrs_to_tokens(r#static.attrs, options, &mut tokens);
 
 			r#static.vis.to_tokens(&mut tokens);
 			r#static.static_token.to_tokens(&mut tokens);
@@ -153,25 +933,28 @@ fn to_tokens(item: Item) -> TokenStream2 {
 			r#static.semi_token.to_tokens(&mut tokens);
 		},
 
-		Item::Struct(r#struct) => {
-			attrs_to_tokens(r#struct.attrs, &mut tokens);
+		Item::Struct(mut r#struct) => {
+			add_configured_fields(&mut r#struct, options, &mut tokens);
+			apply_to_fields(&mut r#struct.fields, options);
+			attrs_to_tokens(r#struct.attrs, options, &mut tokens);
 
-			let (impl_generics, _, where_clause) = r#struct.generics.split_for_impl();
+			let (generics, where_clause) = shim::split_generics(&r#struct.generics);
 
 			r#struct.vis.to_tokens(&mut tokens);
 			r#struct.struct_token.to_tokens(&mut tokens);
 			r#struct.ident.to_tokens(&mut tokens);
 
-			impl_generics.to_tokens(&mut tokens);
+			generics.to_tokens(&mut tokens);
 
-			fields_to_tokens(r#struct.fields, where_clause, &mut tokens);
+			fields_to_tokens(r#struct.fields, where_clause, options, &mut tokens);
 			r#struct.semi_token.to_tokens(&mut tokens);
 		},
 
 		Item::Trait(r#trait) => {
-			attrs_to_tokens(r#trait.attrs, &mut tokens);
+			let (outer, inner) = split_inner(r#trait.attrs);
+			attrs_to_tokens(outer, options, &mut tokens);
 
-			let (impl_generics, _, where_clause) = r#trait.generics.split_for_impl();
+			let (generics, where_clause) = shim::split_generics(&r#trait.generics);
 
 			r#trait.vis.to_tokens(&mut tokens);
 			r#trait.unsafety.to_tokens(&mut tokens);
@@ -179,7 +962,7 @@ fn to_tokens(item: Item) -> TokenStream2 {
 			r#trait.trait_token.to_tokens(&mut tokens);
 			r#trait.ident.to_tokens(&mut tokens);
 
-			impl_generics.to_tokens(&mut tokens);
+			generics.to_tokens(&mut tokens);
 
 			r#trait.colon_token.to_tokens(&mut tokens);
 			r#trait.supertraits.to_tokens(&mut tokens);
@@ -187,54 +970,67 @@ fn to_tokens(item: Item) -> TokenStream2 {
 			where_clause.to_tokens(&mut tokens);
 
 			r#trait.brace_token.surround(&mut tokens, |tokens| {
-				for item in r#trait.items {
+				attrs_to_tokens(inner, options, tokens);
+
+				let items = r#trait
+					.items
+					.into_iter()
+					.map(|mut item| {
+						if let Some((attrs, _)) = trait_item_parts(&mut item) {
+							attrs.splice(0..0, options.apply_to_members.iter().cloned());
+
+							if options.strip {
+								strip_item_helpers(attrs);
+							}
+						}
+
+						item
+					})
+					.flat_map(|item| split_members(item, trait_item_parts, trait_item_body, options, tokens));
+
+				for item in items.collect::<Vec<_>>() {
 					match item {
 						TraitItem::Const(r#const) => {
-							attrs_to_tokens(r#const.attrs, tokens);
-
-							let (impl_generics, _, where_clause) = r#const.generics.split_for_impl();
+							attrs_to_tokens(r#const.attrs, options, tokens);
 
 							r#const.const_token.to_tokens(tokens);
 							r#const.ident.to_tokens(tokens);
-
-							impl_generics.to_tokens(tokens);
-
 							r#const.colon_token.to_tokens(tokens);
 							r#const.ty.to_tokens(tokens);
 							if let Some((eq, expr)) = &r#const.default {
 								eq.to_tokens(tokens);
 								expr.to_tokens(tokens);
 							}
-
-							where_clause.to_tokens(tokens);
-
 							r#const.semi_token.to_tokens(tokens);
 						},
 
 						TraitItem::Fn(r#fn) => {
-							attrs_to_tokens(r#fn.attrs, tokens);
+							let (outer, inner) = split_inner(r#fn.attrs);
+							attrs_to_tokens(outer, options, tokens);
 
-							r#fn.sig.to_tokens(tokens);
-							r#fn.default.to_tokens(tokens);
+							sig_to_tokens(r#fn.sig, options, tokens);
+							if let Some(block) = r#fn.default {
+								block_to_tokens(block, inner, TokenStream2::new(), options, tokens);
+							}
 							r#fn.semi_token.to_tokens(tokens);
 						},
 
 						TraitItem::Macro(r#macro) => {
-							attrs_to_tokens(r#macro.attrs, tokens);
+							attrs_to_tokens(r#macro.attrs, options, tokens);
 
 							r#macro.mac.to_tokens(tokens);
 							r#macro.semi_token.to_tokens(tokens);
 						},
 
 						TraitItem::Type(r#type) => {
-							attrs_to_tokens(r#type.attrs, tokens);
+							attrs_to_tokens(r#type.attrs, options, tokens);
 
-							let (impl_generics, _, where_clause) = r#type.generics.split_for_impl();
+							let (generics, where_clause) = shim::split_generics(&r#type.generics);
 
 							r#type.type_token.to_tokens(tokens);
 							r#type.ident.to_tokens(tokens);
 
-							impl_generics.to_tokens(tokens);
+							generics.to_tokens(tokens);
 
 							r#type.colon_token.to_tokens(tokens);
 							r#type.bounds.to_tokens(tokens);
@@ -250,22 +1046,22 @@ fn to_tokens(item: Item) -> TokenStream2 {
 
 						TraitItem::Verbatim(token_stream) => token_stream.to_tokens(tokens),
 
-						_ => {},
+						other => other.to_tokens(tokens),
 					}
 				}
 			});
 		},
 
 		Item::TraitAlias(alias) => {
-			attrs_to_tokens(alias.attrs, &mut tokens);
+			attrs_to_tokens(alias.attrs, options, &mut tokens);
 
-			let (impl_generics, _, where_clause) = alias.generics.split_for_impl();
+			let (generics, where_clause) = shim::split_generics(&alias.generics);
 
 			alias.vis.to_tokens(&mut tokens);
 			alias.trait_token.to_tokens(&mut tokens);
 			alias.ident.to_tokens(&mut tokens);
 
-			impl_generics.to_tokens(&mut tokens);
+			generics.to_tokens(&mut tokens);
 
 			alias.eq_token.to_tokens(&mut tokens);
 			alias.bounds.to_tokens(&mut tokens);
@@ -276,26 +1072,42 @@ fn to_tokens(item: Item) -> TokenStream2 {
 		},
 
 		Item::Type(r#type) => {
-			attrs_to_tokens(r#type.attrs, &mut tokens);
+			attrs_to_tokens(r#type.attrs, options, &mut tokens);
 
-			let (impl_generics, _, where_clause) = r#type.generics.split_for_impl();
+			let (generics, where_clause) = shim::split_generics(&r#type.generics);
 
 			r#type.vis.to_tokens(&mut tokens);
 			r#type.type_token.to_tokens(&mut tokens);
 			r#type.ident.to_tokens(&mut tokens);
 
-			impl_generics.to_tokens(&mut tokens);
+			generics.to_tokens(&mut tokens);
+
+			// Unlike associated types, type aliases have their where clause before the type.
+			where_clause.to_tokens(&mut tokens);
 
 			r#type.eq_token.to_tokens(&mut tokens);
 			r#type.ty.to_tokens(&mut tokens);
 
+			r#type.semi_token.to_tokens(&mut tokens);
+		},
+
+		Item::Union(union) => {
+			attrs_to_tokens(union.attrs, options, &mut tokens);
+
+			let (generics, where_clause) = shim::split_generics(&union.generics);
+
+			union.vis.to_tokens(&mut tokens);
+			union.union_token.to_tokens(&mut tokens);
+			union.ident.to_tokens(&mut tokens);
+
+			generics.to_tokens(&mut tokens);
 			where_clause.to_tokens(&mut tokens);
 
-			r#type.semi_token.to_tokens(&mut tokens);
+			fields_named_to_tokens(union.fields, options, &mut tokens);
 		},
 
 		Item::Use(r#use) => {
-			attrs_to_tokens(r#use.attrs, &mut tokens);
+			attrs_to_tokens(r#use.attrs, options, &mut tokens);
 
 			r#use.vis.to_tokens(&mut tokens);
 			r#use.use_token.to_tokens(&mut tokens);
@@ -306,46 +1118,960 @@ fn to_tokens(item: Item) -> TokenStream2 {
 
 		Item::Verbatim(token_stream) => token_stream.to_tokens(&mut tokens),
 
-		_ => (),
+		// Other kinds of items aren't rewritten, but are still emitted rather than dropped.
+		mut other => {
+			if contains_helpers(other.to_token_stream()) {
+				let mut span = other.span();
+
+				// The helpers on the item itself are removed, so that they don't cause further errors.
+				if let Some(attrs) = item_attrs(&mut other) {
+					let is_helper =
+						|attribute: &Attribute| HELPERS.iter().any(|helper| is_helper_path(attribute.path(), helper));
+
+					if let Some(attribute) = attrs.iter().find(|attribute| is_helper(attribute)) {
+						span = attribute.path().span();
+					}
+					attrs.retain(|attribute| !is_helper(attribute));
+				}
+
+				let error = Error::new(
+					span,
+					"`#[cfg_attrs]` doesn't support this kind of item yet, so its helper attributes can't be used \
+					 on or within it",
+				);
+				tokens.extend(options.error(error));
+			}
+
+			other.to_tokens(&mut tokens);
+		},
+	}
+
+	// Errors that couldn't be emitted within the item, such as those for fields, go before it.
+	let mut errors = options.errors.take();
+	errors.extend(tokens);
+
+	errors
+}
+
+/// Duplicates the `item` for a `#[configure_cfg(...)]` attribute that was at `index` in its
+/// attributes.
+#[cfg(feature = "full")]
+fn configure_cfg_to_tokens(item: Item, index: usize, attribute: Attribute, options: &Options) -> TokenStream2 {
+	let mut tokens = TokenStream2::new();
+
+	for item in configure_cfg_copies(item, index, attribute, item_parts, options, &mut tokens) {
+		tokens.extend(to_tokens(item, options));
 	}
 
 	tokens
 }
 
-fn attrs_to_tokens(attrs: Vec<Attribute>, tokens: &mut TokenStream2) {
-	for attribute in attrs {
-		Attr::try_from(attribute)
-			.map_or_else(Error::into_compile_error, ToTokens::into_token_stream)
-			.to_tokens(tokens);
+/// Returns the copies of the `item` for a `#[configure_cfg(...)]` attribute that was at `index` in
+/// its attributes, found with `parts`.
+///
+/// The first copy is gated on the condition and has the configured attributes applied directly. The
+/// second copy is gated on the negation of the condition, and has the `else` attributes applied
+/// directly, if there are any. Errors are emitted to `errors`, and the `item` is returned as it is.
+#[cfg(feature = "full")]
+fn configure_cfg_copies<T: Clone>(
+	item: T,
+	index: usize,
+	attribute: Attribute,
+	parts: fn(&mut T) -> ItemParts<'_>,
+	options: &Options,
+	errors: &mut TokenStream2,
+) -> Vec<T> {
+	let span = attribute.path().span();
+
+	let meta = attribute.meta.require_list().and_then(|list| {
+		list.parse_args_with(|input: ParseStream| ConfigureMeta::parse(input, options, 1, Some(attribute.path())))
+	});
+
+	let meta = match meta {
+		Ok(meta) => meta,
+
+		Err(error) => {
+			errors.extend(options.error(error));
+
+			return vec![item];
+		},
+	};
+
+	let mut positive = item.clone();
+	let mut negative = item;
+
+	let condition = &meta.condition;
+	gate_item(
+		&mut positive,
+		parts,
+		index,
+		parse_quote_spanned!(span=> #[cfg(#condition)]),
+		&meta.attrs,
+	);
+
+	let negation = condition.negation();
+	let otherwise = meta.otherwise.as_ref().map_or(&[][..], |(_, attrs)| attrs);
+	gate_item(
+		&mut negative,
+		parts,
+		index,
+		parse_quote_spanned!(span=> #[cfg(#negation)]),
+		otherwise,
+	);
+
+	vec![positive, negative]
+}
+
+/// Duplicates the `item` for each arm of a `#[configure_cfg_if(...)]` attribute that was at `index`
+/// in its attributes.
+#[cfg(feature = "full")]
+fn configure_cfg_if_to_tokens(item: Item, index: usize, attribute: Attribute, options: &Options) -> TokenStream2 {
+	let mut tokens = TokenStream2::new();
+
+	for item in configure_cfg_if_copies(item, index, attribute, item_parts, options, &mut tokens) {
+		tokens.extend(to_tokens(item, options));
+	}
+
+	tokens
+}
+
+/// Returns the copies of the `item` for each arm of a `#[configure_cfg_if(...)]` attribute that was
+/// at `index` in its attributes, found with `parts`.
+///
+/// Each copy is gated on its arm's condition, so that exactly one of them is compiled, and has the
+/// arm's attributes applied directly. Without a `_` arm, there is a final copy with no attributes for
+/// when none of the arms apply. Errors are emitted to `errors`, and the `item` is returned as it is.
+#[cfg(feature = "full")]
+fn configure_cfg_if_copies<T: Clone>(
+	item: T,
+	index: usize,
+	attribute: Attribute,
+	parts: fn(&mut T) -> ItemParts<'_>,
+	options: &Options,
+	errors: &mut TokenStream2,
+) -> Vec<T> {
+	let span = attribute.path().span();
+
+	let arms = attribute.meta.require_list().and_then(|list| {
+		list.parse_args_with(|input: ParseStream| {
+			ConfigureMeta::parse_arms(input, options, 1, Some(attribute.path()), "configure_cfg_if", true)
+		})
+	});
+
+	let arms = match arms {
+		Ok(arms) => arms,
+
+		Err(error) => {
+			errors.extend(options.error(error));
+
+			return vec![item];
+		},
+	};
+
+	arms.iter()
+		.map(|arm| {
+			let mut copy = item.clone();
+
+			let condition = &arm.condition;
+			gate_item(
+				&mut copy,
+				parts,
+				index,
+				parse_quote_spanned!(span=> #[cfg(#condition)]),
+				&arm.attrs,
+			);
+
+			copy
+		})
+		.collect()
+}
+
+/// Returns whether the given `attribute` is a `#[configure(...)]` attribute which configures only
+/// `#[cfg(...)]` attributes, for the `split_cfg` option.
+#[cfg(feature = "full")]
+fn configures_only_cfg(attribute: &Attribute, options: &Options) -> bool {
+	if !is_helper_path(attribute.path(), "configure") {
+		return false;
+	}
+
+	let meta = attribute.meta.require_list().and_then(|list| {
+		list.parse_args_with(|input: ParseStream| ConfigureMeta::parse(input, options, 1, Some(attribute.path())))
+	});
+
+	let Ok(meta) = meta else {
+		return false;
+	};
+
+	let otherwise = meta.otherwise.iter().flat_map(|(_, attrs)| attrs);
+	let mut attrs = meta.attrs.iter().chain(otherwise).peekable();
+
+	attrs.peek().is_some()
+		&& attrs.all(|attr| matches!(attr, Attr::Other(attribute) if attribute.path().is_ident("cfg")))
+}
+
+/// Duplicates the function `item` for a `#[configure_body(...)]` attribute that was at `index` in its
+/// attributes.
+#[cfg(feature = "full")]
+fn configure_body_to_tokens(item: Item, index: usize, attribute: Attribute, options: &Options) -> TokenStream2 {
+	let mut tokens = TokenStream2::new();
+
+	for item in configure_body_copies(item, index, attribute, item_parts, item_body, options, &mut tokens) {
+		tokens.extend(to_tokens(item, options));
+	}
+
+	tokens
+}
+
+/// Returns the copies of the function `item` for a `#[configure_body(...)]` attribute that was at
+/// `index` in its attributes, found with `parts`, and its body, found with `body`.
+///
+/// The first copy is gated on the condition and has the attribute's body. The second copy is gated
+/// on the negation of the condition, and keeps the original body, unless another
+/// `#[configure_body(...)]` attribute replaces it. Errors are emitted to `errors`, and the `item` is
+/// returned as it is.
+#[cfg(feature = "full")]
+fn configure_body_copies<T: Clone>(
+	mut item: T,
+	index: usize,
+	attribute: Attribute,
+	parts: fn(&mut T) -> ItemParts<'_>,
+	body: fn(&mut T) -> Option<&mut Block>,
+	options: &Options,
+	errors: &mut TokenStream2,
+) -> Vec<T> {
+	let span = attribute.path().span();
+
+	let meta = match body(&mut item) {
+		Some(_) => attribute.meta.require_list().and_then(|list| {
+			list.parse_args_with(|input: ParseStream| {
+				let condition = Condition::parse_with(input, options)?;
+				input.parse::<Token![,]>()?;
+				let block: Block = input.parse()?;
+				input.parse::<Option<Token![,]>>()?;
+
+				Ok((condition, block))
+			})
+		}),
+
+		None => Err(Error::new(
+			span,
+			"`configure_body` can only be used on functions with bodies",
+		)),
+	};
+
+	let (condition, block) = match meta {
+		Ok(meta) => meta,
+
+		Err(error) => {
+			errors.extend(options.error(error));
+
+			return vec![item];
+		},
+	};
+
+	let mut positive = item.clone();
+	let mut negative = item;
+
+	// Only the first matching body applies, so the rest are removed from this copy.
+	if let Some((attrs, _)) = parts(&mut positive) {
+		attrs.retain(|attribute| !is_helper_path(attribute.path(), "configure_body"));
+	}
+	if let Some(body) = body(&mut positive) {
+		*body = block;
+	}
+
+	gate_item(
+		&mut positive,
+		parts,
+		index,
+		parse_quote_spanned!(span=> #[cfg(#condition)]),
+		&[],
+	);
+
+	let negation = condition.negation();
+	gate_item(
+		&mut negative,
+		parts,
+		index,
+		parse_quote_spanned!(span=> #[cfg(#negation)]),
+		&[],
+	);
+
+	vec![positive, negative]
+}
+
+/// Splits off each `#[configure_cfg(...)]`, `#[configure_cfg_if(...)]`, `#[configure_body(...)]`, and
+/// `#[configure_where(...)]` attribute of the given associated `item`, duplicating it for each, like
+/// [`to_tokens`] does for items. Errors are emitted to `errors`.
+#[cfg(feature = "full")]
+fn split_members<T: Clone>(
+	mut item: T,
+	parts: fn(&mut T) -> ItemParts<'_>,
+	body: fn(&mut T) -> Option<&mut Block>,
+	options: &Options,
+	errors: &mut TokenStream2,
+) -> Vec<T> {
+	let helper = parts(&mut item).and_then(|(attrs, _)| {
+		["configure_cfg", "configure_cfg_if", "configure_body"]
+			.into_iter()
+			.find_map(|helper| {
+				let index = attrs
+					.iter()
+					.position(|attribute| is_helper_path(attribute.path(), helper))?;
+
+				Some((helper, index, attrs.remove(index)))
+			})
+	});
+
+	let copies = match helper {
+		Some(("configure_cfg", index, attribute)) => {
+			configure_cfg_copies(item, index, attribute, parts, options, errors)
+		},
+		Some(("configure_cfg_if", index, attribute)) => {
+			configure_cfg_if_copies(item, index, attribute, parts, options, errors)
+		},
+		Some((_, index, attribute)) => configure_body_copies(item, index, attribute, parts, body, options, errors),
+
+		None => return split_where(item, parts, options, errors),
+	};
+
+	copies
+		.into_iter()
+		.flat_map(|item| split_members(item, parts, body, options, errors))
+		.collect()
+}
+
+/// Splits off each `#[configure_where(...)]` attribute of the given `item`, duplicating it for each.
+///
+/// The first copy is gated on the condition and has the predicates added to its where clause. The
+/// second copy is gated on the negation of the condition, and doesn't have the predicates. Errors
+/// are emitted to `errors`.
+#[cfg(feature = "full")]
+fn split_where<T: Clone>(
+	mut item: T,
+	parts: fn(&mut T) -> ItemParts<'_>,
+	options: &Options,
+	errors: &mut TokenStream2,
+) -> Vec<T> {
+	let Some((attrs, generics)) = parts(&mut item) else {
+		return vec![item];
+	};
+	let has_generics = generics.is_some();
+	let Some(index) = attrs
+		.iter()
+		.position(|attribute| is_helper_path(attribute.path(), "configure_where"))
+	else {
+		return vec![item];
+	};
+
+	let attribute = attrs.remove(index);
+	let span = attribute.path().span();
+
+	let meta = if has_generics {
+		attribute.parse_args_with(|input: ParseStream| {
+			let condition = Condition::parse_with(input, options)?;
+			input.parse::<Token![,]>()?;
+
+			Ok((
+				condition,
+				Punctuated::<WherePredicate, Token![,]>::parse_terminated(input)?,
+			))
+		})
+	} else {
+		Err(Error::new(
+			span,
+			"`configure_where` can only be used on items with generics",
+		))
+	};
+
+	let (condition, predicates) = match meta {
+		Ok(meta) => meta,
+
+		Err(error) => {
+			errors.extend(options.error(error));
+
+			return split_where(item, parts, options, errors);
+		},
+	};
+
+	let mut positive = item.clone();
+	let mut negative = item;
+
+	if let Some((attrs, generics)) = parts(&mut positive) {
+		attrs.insert(index, parse_quote_spanned!(span=> #[cfg(#condition)]));
+
+		if let Some(generics) = generics {
+			generics.make_where_clause().predicates.extend(predicates);
+		}
+	}
+
+	let negation = condition.negation();
+	if let Some((attrs, _)) = parts(&mut negative) {
+		attrs.insert(index, parse_quote_spanned!(span=> #[cfg(#negation)]));
+	}
+
+	let mut items = split_where(positive, parts, options, errors);
+	items.extend(split_where(negative, parts, options, errors));
+
+	items
+}
+
+/// The attributes and, if it has them, the generics of an item.
+#[cfg(feature = "full")]
+type ItemParts<'item> = Option<(&'item mut Vec<Attribute>, Option<&'item mut Generics>)>;
+
+/// Returns the attributes and generics of the given `item`.
+#[cfg(feature = "full")]
+fn item_parts(item: &mut Item) -> ItemParts<'_> {
+	match item {
+		Item::Const(r#const) => Some((&mut r#const.attrs, None)),
+		Item::Enum(r#enum) => Some((&mut r#enum.attrs, Some(&mut r#enum.generics))),
+		Item::Fn(r#fn) => Some((&mut r#fn.attrs, Some(&mut r#fn.sig.generics))),
+		Item::Impl(r#impl) => Some((&mut r#impl.attrs, Some(&mut r#impl.generics))),
+		Item::Struct(r#struct) => Some((&mut r#struct.attrs, Some(&mut r#struct.generics))),
+		Item::Trait(r#trait) => Some((&mut r#trait.attrs, Some(&mut r#trait.generics))),
+		Item::TraitAlias(alias) => Some((&mut alias.attrs, Some(&mut alias.generics))),
+		Item::Type(r#type) => Some((&mut r#type.attrs, Some(&mut r#type.generics))),
+		Item::Union(union) => Some((&mut union.attrs, Some(&mut union.generics))),
+
+		item => item_attrs(item).map(|attrs| (attrs, None)),
+	}
+}
+
+/// Returns the body of the given `item`, if it is a function.
+#[cfg(feature = "full")]
+fn item_body(item: &mut Item) -> Option<&mut Block> {
+	match item {
+		Item::Fn(r#fn) => Some(&mut r#fn.block),
+
+		_ => None,
+	}
+}
+
+/// Removes the [`ITEM_HELPERS`] from the given `attrs`, for the `strip` option.
+#[cfg(feature = "full")]
+fn strip_item_helpers(attrs: &mut Vec<Attribute>) {
+	attrs.retain(|attribute| {
+		!ITEM_HELPERS
+			.iter()
+			.any(|helper| is_helper_path(attribute.path(), helper))
+	});
+}
+
+/// Returns the attributes and generics of the given impl `item`.
+#[cfg(feature = "full")]
+fn impl_item_parts(item: &mut ImplItem) -> ItemParts<'_> {
+	match item {
+		ImplItem::Const(r#const) => Some((&mut r#const.attrs, None)),
+		ImplItem::Fn(r#fn) => Some((&mut r#fn.attrs, Some(&mut r#fn.sig.generics))),
+		ImplItem::Type(r#type) => Some((&mut r#type.attrs, Some(&mut r#type.generics))),
+		ImplItem::Macro(r#macro) => Some((&mut r#macro.attrs, None)),
+
+		_ => None,
+	}
+}
+
+/// Returns the body of the given impl `item`, if it is a function.
+#[cfg(feature = "full")]
+fn impl_item_body(item: &mut ImplItem) -> Option<&mut Block> {
+	match item {
+		ImplItem::Fn(r#fn) => Some(&mut r#fn.block),
+
+		_ => None,
+	}
+}
+
+/// Returns the attributes and generics of the given trait `item`.
+#[cfg(feature = "full")]
+fn trait_item_parts(item: &mut TraitItem) -> ItemParts<'_> {
+	match item {
+		TraitItem::Const(r#const) => Some((&mut r#const.attrs, None)),
+		TraitItem::Fn(r#fn) => Some((&mut r#fn.attrs, Some(&mut r#fn.sig.generics))),
+		TraitItem::Type(r#type) => Some((&mut r#type.attrs, Some(&mut r#type.generics))),
+		TraitItem::Macro(r#macro) => Some((&mut r#macro.attrs, None)),
+
+		_ => None,
+	}
+}
+
+/// Returns the body of the given trait `item`, if it is a provided function.
+#[cfg(feature = "full")]
+fn trait_item_body(item: &mut TraitItem) -> Option<&mut Block> {
+	match item {
+		TraitItem::Fn(r#fn) => r#fn.default.as_mut(),
+
+		_ => None,
+	}
+}
+
+/// Removes each `#[configure_field(...)]` attribute from the given struct, adding its fields gated on
+/// its condition. Errors are emitted to `errors`.
+#[cfg(feature = "full")]
+fn add_configured_fields(r#struct: &mut ItemStruct, options: &Options, errors: &mut TokenStream2) {
+	let (configure_fields, attrs) = mem::take(&mut r#struct.attrs)
+		.into_iter()
+		.partition(|attribute| is_helper_path(attribute.path(), "configure_field"));
+	r#struct.attrs = attrs;
+
+	for attribute in configure_fields {
+		let span = attribute.path().span();
+
+		let fields = match &r#struct.fields {
+			Fields::Named(_) => attribute.meta.require_list().and_then(|list| {
+				list.parse_args_with(|input: ParseStream| parse_configured_fields(input, Field::parse_named, options))
+			}),
+			Fields::Unnamed(_) => attribute.meta.require_list().and_then(|list| {
+				list.parse_args_with(|input: ParseStream| parse_configured_fields(input, Field::parse_unnamed, options))
+			}),
+
+			Fields::Unit => Err(Error::new(
+				span,
+				"`configure_field` can't be used on unit structs, as they have no fields to add to",
+			)),
+		};
+
+		let (condition, fields) = match fields {
+			Ok(fields) => fields,
+
+			Err(error) => {
+				errors.extend(options.error(error));
+				continue;
+			},
+		};
+
+		let cfg: Attribute = parse_quote_spanned!(span=> #[cfg(#condition)]);
+		let existing = match &mut r#struct.fields {
+			Fields::Named(named) => &mut named.named,
+			Fields::Unnamed(unnamed) => &mut unnamed.unnamed,
+			Fields::Unit => unreachable!("unit structs are rejected above"),
+		};
+
+		let trailing_comma = existing.trailing_punct();
+
+		for mut field in fields {
+			field.attrs.insert(0, cfg.clone());
+			existing.push(field);
+		}
+
+		if trailing_comma {
+			existing.push_punct(Token![,](span));
+		}
+	}
+}
+
+/// Parses a `configure_field` attribute's condition and fields, which are parsed with `parse_field`.
+#[cfg(feature = "full")]
+fn parse_configured_fields(
+	input: ParseStream,
+	parse_field: fn(ParseStream) -> syn::Result<Field>,
+	options: &Options,
+) -> syn::Result<(Condition, Punctuated<Field, Token![,]>)> {
+	let condition = Condition::parse_with(input, options)?;
+	input.parse::<Token![,]>()?;
+
+	Ok((condition, Punctuated::parse_terminated_with(input, parse_field)?))
+}
+
+/// Inserts the `cfg` attribute, followed by the configured `attrs`, at `index` in the `item`'s
+/// attributes, found with `parts`.
+#[cfg(feature = "full")]
+fn gate_item<T>(item: &mut T, parts: fn(&mut T) -> ItemParts<'_>, index: usize, cfg: Attribute, attrs: &[Attr]) {
+	if let Some((item_attrs, _)) = parts(item) {
+		// Any nested `configure` attributes are rewritten by `Attr`'s `ToTokens` implementation.
+		let tokens = attrs.iter().map(ToTokens::to_token_stream).collect();
+		let attrs = shim::parse_outer_attrs
+			.parse2(tokens)
+			.expect("rewritten attributes are valid attributes");
+
+		item_attrs.splice(index..index, iter::once(cfg).chain(attrs));
+	}
+}
+
+/// Returns the attributes of the given `item`, if it has any.
+#[cfg(feature = "full")]
+fn item_attrs(item: &mut Item) -> Option<&mut Vec<Attribute>> {
+	match item {
+		Item::Const(r#const) => Some(&mut r#const.attrs),
+		Item::Enum(r#enum) => Some(&mut r#enum.attrs),
+		Item::ExternCrate(r#extern) => Some(&mut r#extern.attrs),
+		Item::Fn(r#fn) => Some(&mut r#fn.attrs),
+		Item::ForeignMod(foreign) => Some(&mut foreign.attrs),
+		Item::Impl(r#impl) => Some(&mut r#impl.attrs),
+		Item::Macro(r#macro) => Some(&mut r#macro.attrs),
+		Item::Mod(r#mod) => Some(&mut r#mod.attrs),
+		Item::Static(r#static) => Some(&mut r#static.attrs),
+		Item::Struct(r#struct) => Some(&mut r#struct.attrs),
+		Item::Trait(r#trait) => Some(&mut r#trait.attrs),
+		Item::TraitAlias(alias) => Some(&mut alias.attrs),
+		Item::Type(r#type) => Some(&mut r#type.attrs),
+		Item::Union(union) => Some(&mut union.attrs),
+		Item::Use(r#use) => Some(&mut r#use.attrs),
+
+		_ => None,
+	}
+}
+
+fn attrs_to_tokens(attrs: Vec<Attribute>, options: &Options, tokens: &mut TokenStream2) {
+	for attr in convert_attrs(attrs, options) {
+		match attr {
+			Ok(attr) => attr_to_tokens(attr, options, tokens),
+			Err(error) => tokens.extend(options.error(error)),
+		}
+	}
+}
+
+/// Like [`attrs_to_tokens`], but for the attributes of fields and variants, where a
+/// `compile_error!` can't be emitted in place. Errors are emitted before the item instead, so that
+/// the rest of the item is unaffected.
+fn field_attrs_to_tokens(attrs: Vec<Attribute>, options: &Options, tokens: &mut TokenStream2) {
+	for attr in convert_attrs(attrs, options) {
+		match attr {
+			Ok(attr) => attr_to_tokens(attr, options, tokens),
+			Err(error) => {
+				let error = options.error(error);
+				options.errors.borrow_mut().extend(error);
+			},
+		}
+	}
+}
+
+/// Emits the given `attr`. With the `mixed_site` option, the tokens of rewritten attributes are
+/// given [mixed-site](Span::mixed_site) hygiene, while keeping their locations.
+fn attr_to_tokens(attr: Attr, options: &Options, tokens: &mut TokenStream2) {
+	match attr {
+		Attr::Configure { .. } | Attr::Arms { .. } if options.mixed_site => {
+			tokens.extend(resolve_at_mixed_site(attr.into_token_stream()));
+		},
+
+		attr => attr.to_tokens(tokens),
+	}
+}
+
+/// Resolves each of the given `tokens`, including those within groups, at [`Span::mixed_site`].
+fn resolve_at_mixed_site(tokens: TokenStream2) -> TokenStream2 {
+	tokens
+		.into_iter()
+		.map(|mut tree| {
+			if let TokenTree::Group(group) = &tree {
+				let mut resolved = Group::new(group.delimiter(), resolve_at_mixed_site(group.stream()));
+				resolved.set_span(group.span());
+
+				tree = TokenTree::Group(resolved);
+			}
+
+			tree.set_span(tree.span().resolved_at(Span::mixed_site()));
+			tree
+		})
+		.collect()
+}
+
+/// Converts the given `attrs`, warning about duplicates and sorting them if enabled.
+///
+/// With the `strip` option, the configured attributes are removed before anything else, so they
+/// aren't warned about or marked.
+fn convert_attrs(attrs: Vec<Attribute>, options: &Options) -> Vec<syn::Result<Attr>> {
+	let mut attrs: Vec<_> = attrs
+		.into_iter()
+		.filter_map(|attribute| {
+			#[cfg(feature = "audit")]
+			let original = attribute.clone();
+
+			let attr = Attr::from_attribute(attribute, options, 0);
+
+			match &attr {
+				Ok(Attr::Configure { .. } | Attr::Arms { .. }) if options.strip => return None,
+
+				#[cfg(feature = "audit")]
+				Ok(attr @ (Attr::Configure { .. } | Attr::Arms { .. })) => options.mark(&original, attr),
+
+				_ => {},
+			}
+
+			Some(attr)
+		})
+		.collect();
+
+	if options.warn_duplicates {
+		warn_duplicates(&attrs, options);
+	}
+
+	if options.sort {
+		sort_configured(&mut attrs);
+	}
+
+	attrs
+}
+
+/// Sorts each run of consecutive configured attributes in the given `attrs` by the `cfg_attr`
+/// attributes they are rewritten to, which orders them by condition first.
+///
+/// Nothing is moved past an unconditional attribute or an error, and configured attributes which
+/// configure doc comments or derives aren't moved at all, since their order is significant.
+fn sort_configured(attrs: &mut [syn::Result<Attr>]) {
+	fn sort_key(attr: &syn::Result<Attr>) -> Option<String> {
+		let mut configured = Vec::new();
+		let attr = match attr {
+			Ok(attr @ Attr::Configure { meta, .. }) => {
+				meta.configured_attrs(&mut configured);
+				attr
+			},
+			Ok(attr @ Attr::Arms { arms, .. }) => {
+				arms.iter().for_each(|arm| arm.configured_attrs(&mut configured));
+				attr
+			},
+
+			_ => return None,
+		};
+
+		let significant = configured
+			.iter()
+			.any(|attribute| attribute.path().is_ident("doc") || attribute.path().is_ident("derive"));
+
+		(!significant).then(|| attr.to_token_stream().to_string())
+	}
+
+	for run in attrs.split_mut(|attr| sort_key(attr).is_none()) {
+		run.sort_by_cached_key(sort_key);
+	}
+}
+
+/// Warns about configured attributes which exactly match an unconditional attribute in the same
+/// `attrs`, since they would be applied twice if the condition holds.
+///
+/// Doc comments are ignored, as repeated lines (such as blank lines) are common and harmless.
+fn warn_duplicates(attrs: &[syn::Result<Attr>], options: &Options) {
+	let unconditional: Vec<_> = attrs
+		.iter()
+		.filter_map(|attr| match attr {
+			Ok(Attr::Other(attribute)) if !attribute.path().is_ident("doc") => {
+				Some(attribute.meta.to_token_stream().to_string())
+			},
+
+			_ => None,
+		})
+		.collect();
+
+	if unconditional.is_empty() {
+		return;
+	}
+
+	let mut configured = Vec::new();
+	for attr in attrs.iter().flatten() {
+		match attr {
+			Attr::Configure { meta, .. } => meta.configured_attrs(&mut configured),
+			Attr::Arms { arms, .. } => arms.iter().for_each(|arm| arm.configured_attrs(&mut configured)),
+			Attr::Other(_) => {},
+		}
+	}
+
+	for attribute in configured {
+		if unconditional.contains(&attribute.meta.to_token_stream().to_string()) {
+			options.warn(
+				attribute.span(),
+				&format!(
+					"`#[{}]` is configured, but it is already applied unconditionally",
+					attribute.meta.to_token_stream(),
+				),
+			);
+		}
+	}
+}
+
+/// Splits the given `attrs` into their outer attributes and their inner attributes.
+#[cfg(feature = "full")]
+fn split_inner(attrs: Vec<Attribute>) -> (Vec<Attribute>, Vec<Attribute>) {
+	attrs
+		.into_iter()
+		.partition(|attribute| matches!(attribute.style, AttrStyle::Outer))
+}
+
+/// Emits the given function signature `sig`, rewriting the attributes of its parameters.
+#[cfg(feature = "full")]
+fn sig_to_tokens(sig: Signature, options: &Options, tokens: &mut TokenStream2) {
+	sig.constness.to_tokens(tokens);
+	sig.asyncness.to_tokens(tokens);
+	sig.unsafety.to_tokens(tokens);
+	sig.abi.to_tokens(tokens);
+	sig.fn_token.to_tokens(tokens);
+	sig.ident.to_tokens(tokens);
+
+	let (generics, where_clause) = shim::split_generics(&sig.generics);
+	generics.to_tokens(tokens);
+
+	sig.paren_token.surround(tokens, |tokens| {
+		let trailing = sig.inputs.empty_or_trailing();
+
+		for pair in sig.inputs.into_pairs() {
+			let (mut input, comma) = match pair {
+				Pair::Punctuated(input, comma) => (input, Some(comma)),
+				Pair::End(input) => (input, None),
+			};
+
+			let attrs = match &mut input {
+				FnArg::Receiver(receiver) => &mut receiver.attrs,
+				FnArg::Typed(typed) => &mut typed.attrs,
+			};
+			field_attrs_to_tokens(mem::take(attrs), options, tokens);
+
+			input.to_tokens(tokens);
+			comma.to_tokens(tokens);
+		}
+
+		if let Some(variadic) = &sig.variadic {
+			if !trailing {
+				<Token![,]>::default().to_tokens(tokens);
+			}
+
+			variadic.to_tokens(tokens);
+		}
+	});
+
+	sig.output.to_tokens(tokens);
+	where_clause.to_tokens(tokens);
+}
+
+/// Emits the given `block`, with the `inner` attributes and `warnings` of the item it belongs to at
+/// its start.
+///
+/// Items and `let` statements directly within the block are processed too, but any other statements
+/// (and nested blocks) are emitted as they are. Their warnings go within the block, before them.
+#[cfg(feature = "full")]
+fn block_to_tokens(
+	block: Block,
+	inner: Vec<Attribute>,
+	mut warnings: TokenStream2,
+	options: &Options,
+	tokens: &mut TokenStream2,
+) {
+	block.brace_token.surround(tokens, |tokens| {
+		// Inner attributes must come before anything else in the block.
+		warnings.extend(options.warnings_of(|| attrs_to_tokens(inner, options, tokens)));
+		tokens.extend(warnings);
+
+		for stmt in block.stmts {
+			let mut stmt_tokens = TokenStream2::new();
+			let warnings = options.warnings_of(|| match stmt {
+				Stmt::Item(item) => options.nested(|| stmt_tokens.extend(to_tokens(item, options))),
+
+				Stmt::Local(mut local) => {
+					attrs_to_tokens(mem::take(&mut local.attrs), options, &mut stmt_tokens);
+					local.to_tokens(&mut stmt_tokens);
+				},
+
+				stmt => stmt.to_tokens(&mut stmt_tokens),
+			});
+
+			tokens.extend(warnings);
+			tokens.extend(stmt_tokens);
+		}
+	});
+}
+
+fn variants_to_tokens(variants: Punctuated<Variant, Token![,]>, options: &Options, tokens: &mut TokenStream2) {
+	for pair in variants.into_pairs() {
+		let (variant, comma) = match pair {
+			Pair::Punctuated(variant, comma) => (variant, Some(comma)),
+			Pair::End(variant) => (variant, None),
+		};
+
+		field_attrs_to_tokens(variant.attrs, options, tokens);
+
+		variant.ident.to_tokens(tokens);
+		fields_to_tokens(variant.fields, None, options, tokens);
+
+		// Variants with fields may have explicit discriminants too, which follow the fields.
+		if let Some((eq, discrim)) = &variant.discriminant {
+			eq.to_tokens(tokens);
+			discrim.to_tokens(tokens);
+		};
+
+		comma.to_tokens(tokens);
+	}
+}
+
+/// Adds the `apply_to_fields` attributes before the attributes of each of the given `fields`.
+///
+/// A field which already has an attribute with the same path as one configured by an
+/// `apply_to_fields` option, whether written directly or configured itself, keeps its own and doesn't
+/// get those of that option.
+fn apply_to_fields(fields: &mut Fields, options: &Options) {
+	if options.apply_to_fields.is_empty() {
+		return;
+	}
+
+	for field in fields.iter_mut() {
+		let own: Vec<_> = field
+			.attrs
+			.iter()
+			.filter_map(|attribute| Attr::from_attribute(attribute.clone(), options, 0).ok())
+			.collect();
+
+		let mut paths = Vec::new();
+		attr_paths(&own, &mut paths);
+
+		let applied = options
+			.apply_to_fields
+			.iter()
+			.filter(|(_, configured)| !configured.iter().any(|path| paths.contains(path)))
+			.map(|(attribute, _)| attribute.clone());
+
+		field.attrs.splice(0..0, applied);
+	}
+}
+
+/// Adds the path of each of the given `attrs`, or of each of the attributes they configure, to
+/// `paths`.
+fn attr_paths(attrs: &[Attr], paths: &mut Vec<String>) {
+	for attr in attrs {
+		match attr {
+			Attr::Configure { meta, .. } => {
+				attr_paths(&meta.attrs, paths);
+
+				if let Some((_, otherwise)) = &meta.otherwise {
+					attr_paths(otherwise, paths);
+				}
+			},
+
+			Attr::Arms { arms, .. } => {
+				for arm in arms {
+					attr_paths(&arm.attrs, paths);
+				}
+			},
+
+			Attr::Other(attribute) => paths.push(attribute.path().to_token_stream().to_string()),
+		}
 	}
 }
 
-fn fields_to_tokens(fields: Fields, where_clause: Option<&WhereClause>, tokens: &mut TokenStream2) {
+fn fields_to_tokens(fields: Fields, where_clause: Option<&WhereClause>, options: &Options, tokens: &mut TokenStream2) {
 	match fields {
 		Fields::Unit => where_clause.to_tokens(tokens),
 
 		Fields::Named(named) => {
 			where_clause.to_tokens(tokens);
-			fields_named_to_tokens(named, tokens)
+			fields_named_to_tokens(named, options, tokens)
 		},
 
-		Fields::Unnamed(unnamed) => unnamed.paren_token.surround(tokens, |tokens| {
-			for pair in unnamed.unnamed.into_pairs() {
-				let (field, comma) = match pair {
-					Pair::Punctuated(field, comma) => (field, Some(comma)),
-					Pair::End(field) => (field, None),
-				};
+		Fields::Unnamed(unnamed) => {
+			unnamed.paren_token.surround(tokens, |tokens| {
+				for pair in unnamed.unnamed.into_pairs() {
+					let (field, comma) = match pair {
+						Pair::Punctuated(field, comma) => (field, Some(comma)),
+						Pair::End(field) => (field, None),
+					};
 
-				field_to_tokens(field, tokens);
-				comma.to_tokens(tokens);
-			}
+					field_to_tokens(field, options, tokens);
+					comma.to_tokens(tokens);
+				}
+			});
 
+			// Tuple structs have their where clause after the fields: `struct S<T>(T) where T: X;`.
 			where_clause.to_tokens(tokens);
-		}),
+		},
 	}
 }
 
-fn fields_named_to_tokens(fields: FieldsNamed, tokens: &mut TokenStream2) {
+fn fields_named_to_tokens(fields: FieldsNamed, options: &Options, tokens: &mut TokenStream2) {
 	fields.brace_token.surround(tokens, |tokens| {
 		for pair in fields.named.into_pairs() {
 			let (field, comma) = match pair {
@@ -353,14 +2079,14 @@ fn fields_named_to_tokens(fields: FieldsNamed, tokens: &mut TokenStream2) {
 				Pair::End(field) => (field, None),
 			};
 
-			field_to_tokens(field, tokens);
+			field_to_tokens(field, options, tokens);
 			comma.to_tokens(tokens);
 		}
 	})
 }
 
-fn field_to_tokens(field: Field, tokens: &mut TokenStream2) {
-	attrs_to_tokens(field.attrs, tokens);
+fn field_to_tokens(field: Field, options: &Options, tokens: &mut TokenStream2) {
+	field_attrs_to_tokens(field.attrs, options, tokens);
 
 	field.vis.to_tokens(tokens);
 	field.ident.to_tokens(tokens);
@@ -368,14 +2094,81 @@ fn field_to_tokens(field: Field, tokens: &mut TokenStream2) {
 	field.ty.to_tokens(tokens);
 }
 
+/// Formats the wrapped tokens as their source representation, for use in `Debug` implementations.
+struct DebugTokens<'tokens, T>(&'tokens T);
+
+impl<T: ToTokens> Debug for DebugTokens<'_, T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "`{}`", self.0.to_token_stream())
+	}
+}
+
+impl Debug for Attr {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Configure { path, meta, .. } => f
+				.debug_struct("Configure")
+				.field("path", &DebugTokens(path))
+				.field("meta", meta)
+				.finish(),
+
+			Self::Arms { path, arms, .. } => f
+				.debug_struct("Arms")
+				.field("path", &DebugTokens(path))
+				.field("arms", arms)
+				.finish(),
+
+			Self::Other(attribute) => f.debug_tuple("Other").field(&DebugTokens(attribute)).finish(),
+		}
+	}
+}
+
+impl Debug for ConfigureMeta {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ConfigureMeta")
+			.field("condition", &DebugTokens(&self.condition))
+			.field("attrs", &self.attrs)
+			.field("otherwise", &self.otherwise.as_ref().map(|(_, attrs)| attrs))
+			.finish()
+	}
+}
+
+/// Displays the rewritten attribute(s).
+impl Display for Attr {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.to_token_stream())
+	}
+}
+
+/// Displays the rewritten `cfg_attr` meta, without the `else` clause.
+impl Display for ConfigureMeta {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.to_token_stream())
+	}
+}
+
 impl ToTokens for Attr {
 	fn to_tokens(&self, tokens: &mut TokenStream2) {
 		match self {
 			Self::Configure {
-				hash, square_bracket, ..
+				hash,
+				style,
+				square_bracket,
+				..
+			}
+			| Self::Arms {
+				hash,
+				style,
+				square_bracket,
+				..
 			} => {
-				hash.to_tokens(tokens);
-				square_bracket.surround(tokens, |tokens| self.meta_to_tokens(tokens));
+				for meta in self.metas() {
+					hash.to_tokens(tokens);
+					if let AttrStyle::Inner(bang) = style {
+						bang.to_tokens(tokens);
+					}
+					square_bracket.surround(tokens, |tokens| meta.to_tokens(tokens));
+				}
 			},
 
 			Self::Other(attribute) => attribute.to_tokens(tokens),
@@ -384,86 +2177,787 @@ impl ToTokens for Attr {
 }
 
 impl Attr {
-	fn meta_to_tokens(&self, tokens: &mut TokenStream2) {
+	/// Returns the metas of the attributes this attribute expands to.
+	///
+	/// A `configure` attribute with an `else` clause expands to two `cfg_attr` attributes: one for
+	/// the condition, followed by one for its negation. A `cfg_attr` attribute which wouldn't expand
+	/// to any attributes is left out, since `rustc` warns about it.
+	fn metas(&self) -> Vec<TokenStream2> {
 		match self {
+			// The emitted tokens are spanned at the original tokens they come from, so that the output
+			// can be traced back to (and formats like) the input.
 			Self::Configure { path, meta, .. } => {
-				let path = quote_spanned!(path.span()=> cfg_attr);
-				quote!(#path(#meta)).to_tokens(tokens);
+				let span = path.span();
+				let path = quote_spanned!(span=> cfg_attr);
+				let mut metas = Vec::new();
+
+				if has_metas(&meta.attrs) {
+					metas.push(quote_spanned!(span=> #path(#meta)));
+				}
+
+				if let Some((_, attrs)) = meta.otherwise.as_ref().filter(|(_, attrs)| has_metas(attrs)) {
+					let negation = meta.condition.negation();
+					let comma = meta.comma;
+
+					let mut tokens = quote!(#negation #comma);
+					metas_to_tokens(attrs, comma.span, &mut tokens);
+
+					metas.push(quote_spanned!(span=> #path(#tokens)));
+				}
+
+				metas
 			},
 
-			Self::Other(Attribute { meta, .. }) => meta.to_tokens(tokens),
+			Self::Arms { path, arms, .. } => {
+				let span = path.span();
+				let path = quote_spanned!(span=> cfg_attr);
+
+				arms.iter()
+					.filter(|arm| has_metas(&arm.attrs))
+					.map(|arm| quote_spanned!(span=> #path(#arm)))
+					.collect()
+			},
+
+			Self::Other(Attribute { meta, .. }) => vec![meta.to_token_stream()],
+		}
+	}
+}
+
+impl ConfigureMeta {
+	/// Collects the attributes configured by this meta, including those in its `else` clause and
+	/// those configured by nested `configure` attributes.
+	fn configured_attrs<'meta>(&'meta self, configured: &mut Vec<&'meta Attribute>) {
+		let otherwise = self.otherwise.iter().flat_map(|(_, attrs)| attrs);
+
+		for attr in self.attrs.iter().chain(otherwise) {
+			match attr {
+				Attr::Configure { meta, .. } => meta.configured_attrs(configured),
+				Attr::Arms { arms, .. } => arms.iter().for_each(|arm| arm.configured_attrs(configured)),
+				Attr::Other(attribute) => configured.push(attribute),
+			}
 		}
 	}
 }
 
 impl ToTokens for ConfigureMeta {
 	fn to_tokens(&self, tokens: &mut TokenStream2) {
-		let attrs = self.attrs.pairs().map(|pair| match pair {
-			Pair::Punctuated(attr, comma) => (attr, Some(comma)),
-			Pair::End(attr) => (attr, None),
-		});
-
 		self.condition.to_tokens(tokens);
 		self.comma.to_tokens(tokens);
 
-		for (attr, comma) in attrs {
-			attr.meta_to_tokens(tokens);
-			comma.to_tokens(tokens);
+		metas_to_tokens(&self.attrs, self.comma.span, tokens);
+	}
+}
+
+/// Returns whether any of the given `attrs` expand to any metas.
+fn has_metas(attrs: &[Attr]) -> bool {
+	attrs.iter().any(|attr| !attr.metas().is_empty())
+}
+
+/// Emits the metas of the given `attrs`, separated by commas spanned at `comma_span`.
+fn metas_to_tokens(attrs: &[Attr], comma_span: Span, tokens: &mut TokenStream2) {
+	tokens.append_separated(attrs.iter().flat_map(Attr::metas), Token![,](comma_span));
+}
+
+impl ToTokens for Condition {
+	fn to_tokens(&self, tokens: &mut TokenStream2) {
+		match &self.not {
+			Some(not) => {
+				let meta = &self.meta;
+				let span = not.span;
+
+				quote_spanned!(span=> not(#meta)).to_tokens(tokens);
+			},
+
+			None => self.meta.to_tokens(tokens),
 		}
 	}
 }
 
-impl TryFrom<Attribute> for Attr {
-	type Error = Error;
+impl Condition {
+	/// Returns the negation of this condition.
+	///
+	/// The negation of a condition negated with `!` is the original predicate, rather than a double
+	/// negation.
+	fn negation(&self) -> TokenStream2 {
+		let meta = &self.meta;
+
+		match &self.not {
+			Some(_) => meta.to_token_stream(),
+			None => quote_spanned!(meta.span()=> not(#meta)),
+		}
+	}
+}
 
-	fn try_from(attribute: Attribute) -> syn::Result<Self> {
-		Ok(if attribute.path().is_ident("configure") {
-			let (path, meta) = match attribute.meta {
-				Meta::List(list) => (list.path, syn::parse2(list.tokens)?),
-				other => {
-					return Err(Error::new(
-						other.span(),
-						"expected attribute arguments in parentheses: `configure(...)`",
-					))
-				},
-			};
+impl Attr {
+	/// Converts the given `attribute`, which is nested within `depth` `configure` attributes.
+	fn from_attribute(attribute: Attribute, options: &Options, depth: usize) -> syn::Result<Self> {
+		let Some(name) = ["configure", "configure_if", "configure_each"]
+			.into_iter()
+			.find(|name| is_helper_path(attribute.path(), name))
+		else {
+			if options.deny_unknown {
+				check_unknown(&attribute)?;
+			}
+
+			return Ok(Attr::Other(attribute));
+		};
+
+		if depth >= options.recursion_limit {
+			// Every attribute this is nested within would otherwise add a note.
+			options.noted.set(true);
+
+			return Err(Error::new(
+				attribute.path().span(),
+				format!(
+					"`configure` attributes are nested too deeply (the limit is {}, which can be raised with \
+					 `#[cfg_attrs(recursion_limit = ...)]`)",
+					options.recursion_limit,
+				),
+			));
+		}
+
+		let list = match attribute.meta {
+			Meta::List(list) => list,
+			other => {
+				return Err(Error::new(
+					other.span(),
+					format!("expected attribute arguments in parentheses: `{name}(...)`"),
+				))
+			},
+		};
+
+		Ok(if name == "configure" {
+			let parser = |input: ParseStream| ConfigureMeta::parse(input, options, depth + 1, Some(&list.path));
+			let meta = parser.parse2(list.tokens)?;
 
 			Attr::Configure {
 				hash: attribute.pound_token,
+				style: attribute.style,
 				square_bracket: attribute.bracket_token,
-				path,
+				path: list.path,
 				meta,
 			}
 		} else {
-			Attr::Other(attribute)
+			let arms = if name == "configure_if" {
+				let parser = |input: ParseStream| {
+					ConfigureMeta::parse_arms(input, options, depth + 1, Some(&list.path), "configure_if", false)
+				};
+				parser.parse2(list.tokens)?
+			} else {
+				let parser =
+					|input: ParseStream| ConfigureMeta::parse_rows(input, options, depth + 1, Some(&list.path));
+				parser.parse2(list.tokens)?
+			};
+
+			Attr::Arms {
+				hash: attribute.pound_token,
+				style: attribute.style,
+				square_bracket: attribute.bracket_token,
+				path: list.path,
+				arms,
+			}
 		})
 	}
 }
 
+/// Returns an error if the given `attribute` looks like a misspelled helper attribute: that is, if
+/// its name starts with `configure` or `cfg_attrs` but it isn't one of the [`HELPERS`] (or
+/// `cfg_attrs` itself).
+fn check_unknown(attribute: &Attribute) -> syn::Result<()> {
+	let Some(ident) = attribute.path().get_ident() else {
+		return Ok(());
+	};
+	let name = ident.to_string();
+
+	if (name.starts_with("configure") || name.starts_with("cfg_attrs"))
+		&& name != "cfg_attrs"
+		&& !HELPERS.contains(&name.as_str())
+	{
+		return Err(Error::new(
+			ident.span(),
+			format!("unknown `cfg_attrs` helper attribute: `{name}`"),
+		));
+	}
+
+	Ok(())
+}
+
 impl Attr {
-	fn parse(input: ParseStream) -> syn::Result<Vec<Self>> {
-		let attributes = input.call(Attribute::parse_outer)?;
+	/// Parses attributes nested within `depth` `configure` attributes, the innermost of which is the
+	/// `parent` helper attribute, if any.
+	///
+	/// Errors are given a note pointing at the `parent`, so that it is clear which attribute they
+	/// were found in when attributes are nested deeply. Only the innermost `parent` is noted, and the
+	/// error for nesting too deeply isn't noted at all, as recorded by [`Options::noted`].
+	fn parse(input: ParseStream, options: &Options, depth: usize, parent: Option<&Path>) -> syn::Result<Vec<Self>> {
+		// Any error returned from here on is from within these attributes.
+		options.noted.set(false);
+
+		let in_parent = |mut error: Error| {
+			if let (Some(parent), false) = (parent, options.noted.replace(true)) {
+				let name = parent.segments.last().map(|segment| segment.ident.to_string());
+				let name = name.as_deref().unwrap_or("configure");
+
+				error.combine(Error::new(parent.span(), format!("in this `{name}` attribute")));
+			}
+
+			error
+		};
+
+		let attributes = input.call(shim::parse_outer_attrs).map_err(in_parent)?;
 		let mut attrs = Vec::with_capacity(attributes.len());
 
 		for attribute in attributes {
-			attrs.push(attribute.try_into()?);
+			attrs.push(Attr::from_attribute(attribute, options, depth).map_err(in_parent)?);
 		}
 
 		Ok(attrs)
 	}
 }
 
-impl Parse for ConfigureMeta {
+impl Parse for Options {
 	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let mut options = Self::default();
+
+		while !input.is_empty() {
+			let option = input.call(Ident::parse_any)?;
+
+			match option.to_string().as_str() {
+				"recursion_limit" => {
+					input.parse::<Token![=]>()?;
+					options.recursion_limit = input.parse::<LitInt>()?.base10_parse()?;
+				},
+
+				"warn_duplicates" => options.warn_duplicates = true,
+				"deny_unknown" => options.deny_unknown = true,
+				"strip" => options.strip = true,
+				"sort" => options.sort = true,
+				"features_only" => options.features_only = true,
+				"mixed_site" => options.mixed_site = true,
+				#[cfg(feature = "full")]
+				"split_cfg" => options.split_cfg = true,
+
+				#[cfg(feature = "full")]
+				"apply_to_members" => {
+					let content;
+					syn::parenthesized!(content in input);
+					let meta: TokenStream2 = content.parse()?;
+
+					// The attribute is checked here, so that it is an error even if the trait has no items.
+					let parser = |input: ParseStream| ConfigureMeta::parse(input, &options, 1, None);
+					parser.parse2(meta.clone())?;
+
+					let span = option.span();
+					options
+						.apply_to_members
+						.push(parse_quote_spanned!(span=> #[configure(#meta)]));
+				},
+
+				"apply_to_fields" => {
+					let content;
+					syn::parenthesized!(content in input);
+					let meta: TokenStream2 = content.parse()?;
+
+					let span = option.span();
+					let attribute: Attribute = parse_quote_spanned!(span=> #[configure(#meta)]);
+
+					// Parsing the attribute here finds the paths it configures, and makes it an error even if
+					// the struct has no fields.
+					let mut paths = Vec::new();
+					attr_paths(&[Attr::from_attribute(attribute.clone(), &options, 0)?], &mut paths);
+
+					options.apply_to_fields.push((attribute, paths));
+				},
+
+				_ => {
+					return Err(Error::new(
+						option.span(),
+						format!("unknown `cfg_attrs` option: `{option}`"),
+					))
+				},
+			}
+
+			if !input.is_empty() {
+				input.parse::<Token![,]>()?;
+			}
+		}
+
+		Ok(options)
+	}
+}
+
+impl Parse for Condition {
+	fn parse(input: ParseStream) -> syn::Result<Self> {
+		let not = input.parse()?;
+
+		let meta = if input.peek(Token![$]) {
+			input.parse::<Token![$]>()?;
+			feature_meta(&input.call(Ident::parse_any)?)
+		} else {
+			let mut meta = input.parse()?;
+
+			if let Meta::List(list) = &mut meta {
+				list.tokens = expand_feature_sigils(list.tokens.clone())?;
+			}
+
+			meta
+		};
+
+		Ok(Self { not, meta })
+	}
+}
+
+impl Condition {
+	/// Parses a condition, treating bare identifiers as features with the `features_only` option.
+	fn parse_with(input: ParseStream, options: &Options) -> syn::Result<Self> {
+		let mut condition: Self = input.parse()?;
+
+		if options.features_only {
+			condition.meta = bare_features(condition.meta);
+		}
+
+		Ok(condition)
+	}
+}
+
+/// The names of the configuration options set by `rustc` which are used as bare identifiers, which
+/// the `features_only` option leaves as they are.
+const WELL_KNOWN_CFGS: &[&str] = &[
+	"clippy",
+	"contract_checks",
+	"debug_assertions",
+	"doc",
+	"doctest",
+	"miri",
+	"overflow_checks",
+	"proc_macro",
+	"rustfmt",
+	"sanitizer_cfi_generalize_pointers",
+	"sanitizer_cfi_normalize_integers",
+	"target_has_atomic",
+	"target_has_atomic_equal_alignment",
+	"target_has_atomic_load_store",
+	"target_thread_local",
+	"test",
+	"ub_checks",
+	"unix",
+	"windows",
+];
+
+/// Replaces each bare identifier in the given condition `meta`, including those within `all(...)`,
+/// `any(...)`, and `not(...)`, with the `feature = "..."` predicate for that feature, for the
+/// `features_only` option.
+///
+/// The [`WELL_KNOWN_CFGS`] are left as they are, and a raw identifier is emitted without its `r#`
+/// as a configuration option rather than a feature, for other options like `--cfg` flags.
+fn bare_features(meta: Meta) -> Meta {
+	match meta {
+		Meta::Path(path) => match path.get_ident() {
+			Some(ident) if ident.to_string().starts_with("r#") => {
+				Meta::Path(Ident::new(&ident.unraw().to_string(), ident.span()).into())
+			},
+
+			Some(ident) if WELL_KNOWN_CFGS.contains(&ident.to_string().as_str()) => Meta::Path(path),
+
+			Some(ident) => {
+				let name = LitStr::new(&ident.to_string(), ident.span());
+
+				parse_quote_spanned!(ident.span()=> feature = #name)
+			},
+
+			None => Meta::Path(path),
+		},
+
+		Meta::List(mut list) if ["all", "any", "not"].iter().any(|name| list.path.is_ident(name)) => {
+			if let Ok(metas) = list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated) {
+				let metas = metas.into_pairs().map(|pair| {
+					let (meta, comma) = pair.into_tuple();
+					Pair::new(bare_features(meta), comma)
+				});
+
+				list.tokens = metas.collect::<Punctuated<Meta, Token![,]>>().into_token_stream();
+			}
+
+			Meta::List(list)
+		},
+
+		meta => meta,
+	}
+}
+
+/// Returns the `feature = "..."` predicate for the feature named by the given [`$ident`
+/// sigil](expand_feature_sigils), with underscores replaced by hyphens.
+fn feature_meta(ident: &Ident) -> Meta {
+	let name = LitStr::new(&ident.unraw().to_string().replace('_', "-"), ident.span());
+
+	parse_quote_spanned!(ident.span()=> feature = #name)
+}
+
+/// Replaces each `$ident` in the given condition `tokens`, including those within groups, with the
+/// `feature = "..."` predicate for that feature.
+fn expand_feature_sigils(tokens: TokenStream2) -> syn::Result<TokenStream2> {
+	let mut expanded = TokenStream2::new();
+	let mut tokens = tokens.into_iter();
+
+	while let Some(tree) = tokens.next() {
+		match tree {
+			TokenTree::Punct(dollar) if dollar.as_char() == '$' => match tokens.next() {
+				Some(TokenTree::Ident(ident)) => feature_meta(&ident).to_tokens(&mut expanded),
+
+				_ => {
+					return Err(Error::new(
+						dollar.span(),
+						"expected a feature name after `$`: `$feature_name`",
+					))
+				},
+			},
+
+			TokenTree::Group(group) => {
+				let mut expanded_group = Group::new(group.delimiter(), expand_feature_sigils(group.stream())?);
+				expanded_group.set_span(group.span());
+
+				expanded.append(expanded_group);
+			},
+
+			tree => expanded.append(tree),
+		}
+	}
+
+	Ok(expanded)
+}
+
+impl ConfigureMeta {
+	/// Parses a `configure` attribute's meta, which is nested within `depth` `configure` attributes
+	/// (including its own). The `parent` is the helper attribute the meta belongs to, if any, which
+	/// errors in nested attributes point to.
+	fn parse(input: ParseStream, options: &Options, depth: usize, parent: Option<&Path>) -> syn::Result<Self> {
+		let condition = Condition::parse_with(input, options)?;
+
+		if input.is_empty() {
+			return Err(Error::new(
+				condition.span(),
+				"expected `,` after the condition: `configure(<condition>, <attributes>)` (a trailing comma with no \
+				 attributes, `configure(<condition>,)`, configures nothing)",
+			));
+		}
+
 		Ok(Self {
-			condition: input.parse()?,
+			condition,
 			comma: input.parse()?,
-			attrs: input
-				.parse_terminated(Attr::parse, Token![,])
-				.into_iter()
-				.flatten()
-				.flatten()
-				.collect(),
+			attrs: parse_attrs(input, options, depth, parent)?,
+			otherwise: if input.peek(Token![else]) {
+				Some((input.parse()?, parse_attrs(input, options, depth, parent)?))
+			} else {
+				None
+			},
 		})
 	}
+
+	/// Parses the arms of a `configure_if` attribute, which is nested within `depth` `configure`
+	/// attributes (including its own).
+	///
+	/// Each arm's condition is combined with the negation of all the previous arms' conditions, so
+	/// that only the first matching arm applies. A final `_` arm applies if no other arm does. If the
+	/// arms must be `exhaustive` and there is no `_` arm, one without any attributes is added.
+	///
+	/// The `name` of the attribute is used in errors.
+	fn parse_arms(
+		input: ParseStream,
+		options: &Options,
+		depth: usize,
+		parent: Option<&Path>,
+		name: &str,
+		exhaustive: bool,
+	) -> syn::Result<Vec<Self>> {
+		let mut arms = Vec::new();
+		let mut previous: Vec<Condition> = Vec::new();
+		let mut wildcard_seen = false;
+
+		while !input.is_empty() {
+			let wildcard = input.parse::<Option<Token![_]>>()?;
+			let condition = match &wildcard {
+				Some(_) => None,
+				None => Some(Condition::parse_with(input, options)?),
+			};
+
+			let arrow: Token![=>] = input.parse()?;
+			let attrs = parse_attrs(input, options, depth, parent)?;
+
+			let meta: Meta = match (&condition, &previous[..]) {
+				(Some(condition), []) => syn::parse2(condition.to_token_stream())?,
+				(Some(condition), previous) => parse_quote!(all(not(any(#(#previous),*)), #condition)),
+
+				(None, []) => parse_quote!(all()),
+				(None, previous) => parse_quote!(not(any(#(#previous),*))),
+			};
+
+			arms.push(Self {
+				condition: Condition { not: None, meta },
+				comma: Token![,](arrow.spans[0]),
+				attrs,
+				otherwise: None,
+			});
+
+			match condition {
+				Some(condition) => previous.push(condition),
+
+				None => {
+					input.parse::<Option<Token![;]>>()?;
+
+					if !input.is_empty() {
+						return Err(input.error(format!("the `_` arm must be the last arm of `{name}`")));
+					}
+
+					wildcard_seen = true;
+					break;
+				},
+			}
+
+			if !input.is_empty() {
+				input.parse::<Token![;]>()?;
+			}
+		}
+
+		if exhaustive && !wildcard_seen {
+			let meta: Meta = match &previous[..] {
+				[] => parse_quote!(all()),
+				previous => parse_quote!(not(any(#(#previous),*))),
+			};
+
+			arms.push(Self {
+				condition: Condition { not: None, meta },
+				comma: Token![,](Span::call_site()),
+				attrs: Vec::new(),
+				otherwise: None,
+			});
+		}
+
+		Ok(arms)
+	}
+
+	/// Parses the rows of a `configure_each` attribute, which is nested within `depth` `configure`
+	/// attributes (including its own).
+	///
+	/// Unlike the arms of `configure_if`, each row applies whenever its own condition holds.
+	fn parse_rows(
+		input: ParseStream,
+		options: &Options,
+		depth: usize,
+		parent: Option<&Path>,
+	) -> syn::Result<Vec<Self>> {
+		let mut rows = Vec::new();
+
+		while !input.is_empty() {
+			if input.peek(Token![_]) {
+				return Err(
+					input.error("`configure_each` doesn't support `_` rows: use `all()` for a row that always applies")
+				);
+			}
+
+			let condition = Condition::parse_with(input, options)?;
+			let arrow: Token![=>] = input.parse()?;
+
+			rows.push(Self {
+				condition,
+				comma: Token![,](arrow.spans[0]),
+				attrs: parse_attrs(input, options, depth, parent)?,
+				otherwise: None,
+			});
+
+			if !input.is_empty() {
+				input.parse::<Token![;]>()?;
+			}
+		}
+
+		Ok(rows)
+	}
+}
+
+/// Parses configured attributes, optionally separated by commas, up until the end of the `input`, an
+/// `else` token, or a `;` token (which ends a `configure_if` arm or a `configure_each` row).
+fn parse_attrs(input: ParseStream, options: &Options, depth: usize, parent: Option<&Path>) -> syn::Result<Vec<Attr>> {
+	let mut attrs = Vec::new();
+
+	loop {
+		attrs.extend(Attr::parse(input, options, depth, parent)?);
+
+		if input.is_empty() || input.peek(Token![else]) || input.peek(Token![;]) {
+			break;
+		}
+
+		input.parse::<Token![,]>()?;
+	}
+
+	Ok(attrs)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Converts the given `attribute` with the default options.
+	fn attr(attribute: Attribute) -> Attr {
+		Attr::from_attribute(attribute, &Options::default(), 0).unwrap()
+	}
+
+	#[test]
+	fn attr_displays_rewritten_attribute() {
+		let configure = attr(parse_quote!(#[configure(unix, #[inline] else #[cold])]));
+		let configure_if = attr(parse_quote!(#[configure_if(unix => #[inline]; _ => #[cold])]));
+		let other = attr(parse_quote!(#[inline]));
+
+		assert_eq!(
+			configure.to_string(),
+			"# [cfg_attr (unix , inline)] # [cfg_attr (not (unix) , cold)]",
+		);
+		assert_eq!(
+			configure_if.to_string(),
+			"# [cfg_attr (unix , inline)] # [cfg_attr (not (any (unix)) , cold)]",
+		);
+		assert_eq!(other.to_string(), "# [inline]");
+	}
+
+	#[test]
+	fn configure_meta_displays_cfg_attr_meta() {
+		let Attr::Configure { meta, .. } = attr(parse_quote!(#[configure(!unix, #[inline] else #[cold])])) else {
+			panic!("expected a `configure` attribute");
+		};
+
+		assert_eq!(meta.to_string(), "not (unix) , inline");
+		assert_eq!(
+			format!("{meta:?}"),
+			"ConfigureMeta { condition: `not (unix)`, attrs: [Other(`# [inline]`)], otherwise: Some([Other(`# [cold]`)]) }",
+		);
+	}
+
+	#[test]
+	fn try_expand_returns_the_first_error() {
+		let error = try_expand(
+			quote!(),
+			quote! {
+				struct Errors {
+					#[configure(unix)]
+					first: u8,
+					#[configure_if(unix #[a])]
+					second: u8,
+				}
+			},
+		)
+		.unwrap_err();
+
+		assert_eq!(
+			error.to_string(),
+			"expected `,` after the condition: `configure(<condition>, <attributes>)` (a trailing comma with no \
+			 attributes, `configure(<condition>,)`, configures nothing)",
+		);
+
+		let error = try_expand(
+			quote!(apply_to_fields(unix, #[a])),
+			quote!(
+				enum NotStruct {}
+			),
+		)
+		.unwrap_err();
+		assert_eq!(error.to_string(), "`apply_to_fields` can only be used on structs");
+
+		let error = try_expand(
+			quote!(unknown),
+			quote!(
+				struct Options;
+			),
+		)
+		.unwrap_err();
+		assert!(error.to_string().contains("unknown"), "{error}");
+	}
+
+	#[test]
+	fn try_expand_returns_the_expansion() {
+		let tokens = try_expand(
+			quote!(),
+			quote! {
+				#[configure(unix, #[derive(Debug)])]
+				struct Expanded;
+			},
+		)
+		.unwrap()
+		.to_string();
+
+		// With the `audit` feature, the tokens start with a marker.
+		assert!(
+			tokens.ends_with("# [cfg_attr (unix , derive (Debug))] struct Expanded ;"),
+			"{tokens}"
+		);
+	}
+
+	// `cfg_attrs_test_macros` can't depend on `cfg_attrs` for its copy of the helpers, so this checks
+	// that the copy matches instead.
+	#[test]
+	fn test_macros_helpers_match() {
+		let source = include_str!("../test-macros/src/lib.rs");
+		let (_, list) = source.split_once("const HELPERS: &[&str] = &[").unwrap();
+		let (list, _) = list.split_once("];").unwrap();
+
+		let helpers: Vec<_> = list
+			.split(',')
+			.map(|helper| helper.trim().trim_matches('"'))
+			.filter(|helper| !helper.is_empty())
+			.collect();
+
+		assert_eq!(helpers, HELPERS);
+	}
+
+	// The error for the rejected options is emitted before the module, where it can't be seen by
+	// `expands_to`, so this can't be an integration test.
+	#[cfg(feature = "full")]
+	#[test]
+	fn rejected_options_are_not_applied_to_nested_items() {
+		let mut options: Options = parse_quote! {
+			apply_to_members(unix, #[inline]),
+			apply_to_fields(unix, #[allow(unused)])
+		};
+
+		let errors = reject_options(&mut options, false, false).to_string();
+		assert!(
+			errors.contains("`apply_to_members` can only be used on traits"),
+			"{errors}"
+		);
+		assert!(
+			errors.contains("`apply_to_fields` can only be used on structs"),
+			"{errors}"
+		);
+
+		let item = parse_quote! {
+			mod items {
+				trait Members {
+					fn member();
+				}
+
+				struct Fields {
+					field: u8,
+				}
+			}
+		};
+
+		assert_eq!(
+			to_tokens(item, &options).to_string(),
+			"mod items { trait Members { fn member () ; } struct Fields { field : u8 , } }",
+		);
+	}
+
+	// Attribute macros can't be used on modules without a body on stable, so this can't be an
+	// integration test.
+	#[cfg(feature = "full")]
+	#[test]
+	fn bodiless_module_is_rewritten() {
+		let item = parse_quote! {
+			#[configure(unix, #[path = "sys_unix.rs"])]
+			#[configure(windows, #[path = "sys_windows.rs"])]
+			pub mod sys;
+		};
+
+		assert_eq!(
+			to_tokens(item, &Options::default()).to_string(),
+			r#"# [cfg_attr (unix , path = "sys_unix.rs")] # [cfg_attr (windows , path = "sys_windows.rs")] pub mod sys ;"#,
+		);
+	}
 }
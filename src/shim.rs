@@ -0,0 +1,23 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! The `syn` entry points that the rewriting depends on, so that changes in `syn` only need to be
+//! absorbed here.
+
+use syn::parse::ParseStream;
+use syn::{Attribute, Generics, WhereClause};
+
+/// Splits the given `generics` into their parameters and their where clause.
+///
+/// The parameters are emitted as they were written, including any defaults, unlike
+/// [`Generics::split_for_impl`], which is meant for `impl` headers and so drops defaults.
+pub(crate) fn split_generics(generics: &Generics) -> (&Generics, Option<&WhereClause>) {
+	// `Generics`'s `ToTokens` implementation emits only the parameters, not the where clause.
+	(generics, generics.where_clause.as_ref())
+}
+
+/// Parses any number of outer attributes.
+pub(crate) fn parse_outer_attrs(input: ParseStream) -> syn::Result<Vec<Attribute>> {
+	Attribute::parse_outer(input)
+}
@@ -0,0 +1,346 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Helper macros for testing the expansion of `#[cfg_attrs]`.
+
+use proc_macro::{Delimiter, Group, TokenStream, TokenTree};
+
+/// Asserts that the item it is placed on is token-for-token equal to the expected tokens.
+///
+/// When placed directly after `#[cfg_attrs]`, this receives the output of `#[cfg_attrs]` (minus
+/// any attributes before it). Note that `rustc` expands `#[cfg_attr(...)]` attributes on the item
+/// itself before invoking an attribute macro, so only `#[cfg_attr(...)]` attributes nested within
+/// the item (on fields, variants, associated items, etc.) are seen unexpanded.
+///
+/// Spacing is ignored in the comparison. If the tokens match, nothing is emitted; otherwise, a
+/// `compile_error!` showing both the expected and the actual tokens is emitted. The markers emitted
+/// with `cfg_attrs`'s `audit` feature are ignored, so that expansions can be checked with it enabled
+/// too.
+///
+/// As a guard against helper attributes leaking into the output unprocessed, it is also an error
+/// for the actual tokens to contain one of the [`HELPERS`] in attribute position, even if it was
+/// expected. Macro invocations are exempt, since `#[cfg_attrs]` leaves their input untouched.
+///
+/// ```ignore
+/// #[cfg_attrs]
+/// #[expands_to {
+///     struct Example {
+///         #[cfg_attr(feature = "x", doc = " Docs.")]
+///         field: u8,
+///     }
+/// }]
+/// struct Example {
+///     #[configure(
+///         feature = "x",
+///         /// Docs.
+///     )]
+///     field: u8,
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn expands_to(expected: TokenStream, actual: TokenStream) -> TokenStream {
+	if let Some(helper) = leaked_helper(actual.clone()) {
+		let message = format!("`#[{helper}]` was emitted without being processed by `#[cfg_attrs]`");
+
+		return format!("::core::compile_error!({message:?});").parse().unwrap();
+	}
+
+	let expected = normalize(expected);
+	let actual = normalize(strip_markers(actual));
+
+	if expected == actual {
+		TokenStream::new()
+	} else {
+		let message = format!("unexpected expansion\n\nexpected:\n{expected}\n\nactual:\n{actual}");
+
+		format!("::core::compile_error!({message:?});").parse().unwrap()
+	}
+}
+
+/// Emits the item it is placed on unchanged, unless it contains unprocessed helper attributes (see
+/// [`expands_to`]), in which case it is an error.
+///
+/// This stands in for other attribute macros used alongside `#[cfg_attrs]`.
+///
+/// ```ignore
+/// #[cfg_attrs]
+/// #[processed]
+/// #[configure(feature = "x", #[derive(Debug)])]
+/// struct Example;
+/// ```
+#[proc_macro_attribute]
+pub fn processed(_attr: TokenStream, item: TokenStream) -> TokenStream {
+	match leaked_helper(item.clone()) {
+		Some(helper) => {
+			let message = format!("`#[{helper}]` reached another attribute macro before `#[cfg_attrs]`");
+
+			format!("::core::compile_error!({message:?});").parse().unwrap()
+		},
+
+		None => item,
+	}
+}
+
+/// Replaces the body of the function it is placed on with the given expression.
+///
+/// This stands in for attribute macros which change the item they are applied to, so that whether
+/// they were applied can be observed.
+///
+/// ```ignore
+/// #[returns(2)]
+/// fn two() -> u8 {
+///     1
+/// }
+///
+/// assert_eq!(two(), 2);
+/// ```
+#[proc_macro_attribute]
+pub fn returns(value: TokenStream, item: TokenStream) -> TokenStream {
+	let mut tokens: Vec<_> = item.into_iter().collect();
+
+	match tokens.last_mut() {
+		Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => {
+			*body = Group::new(Delimiter::Brace, value);
+		},
+
+		_ => {
+			return "::core::compile_error!(\"expected a function with a body\");"
+				.parse()
+				.unwrap()
+		},
+	}
+
+	tokens.into_iter().collect()
+}
+
+/// Implements a `HELPERS` associated constant listing the arguments of each `#[helper(...)]`
+/// attribute on the type, in the order the derive sees them.
+///
+/// ```ignore
+/// #[derive(HelperOrder)]
+/// #[helper(a)]
+/// #[helper(b)]
+/// struct Example;
+///
+/// assert_eq!(Example::HELPERS, ["a", "b"]);
+/// ```
+#[proc_macro_derive(HelperOrder, attributes(helper))]
+pub fn helper_order(item: TokenStream) -> TokenStream {
+	let mut helpers = Vec::new();
+	let mut name = None;
+
+	let mut tokens = item.into_iter();
+	while let Some(tree) = tokens.next() {
+		match tree {
+			TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+				let mut attribute = group.stream().into_iter();
+
+				if let (Some(TokenTree::Ident(path)), Some(TokenTree::Group(args))) =
+					(attribute.next(), attribute.next())
+				{
+					if path.to_string() == "helper" {
+						helpers.push(normalize(args.stream()));
+					}
+				}
+			},
+
+			TokenTree::Ident(ident) if matches!(ident.to_string().as_str(), "struct" | "enum" | "union") => {
+				name = tokens.next();
+				break;
+			},
+
+			_ => {},
+		}
+	}
+
+	let name = name.expect("expected a type definition");
+	format!("impl {name} {{ const HELPERS: &'static [&'static str] = &{helpers:?}; }}")
+		.parse()
+		.unwrap()
+}
+
+/// Implements a `DOCS` associated constant listing each line of the type's doc comments, in the
+/// order rustdoc sees them.
+///
+/// ```ignore
+/// #[derive(DocOrder)]
+/// /// First.
+/// #[cfg_attr(all(), doc = " Second.")]
+/// struct Example;
+///
+/// assert_eq!(Example::DOCS, [" First.", " Second."]);
+/// ```
+#[proc_macro_derive(DocOrder)]
+pub fn doc_order(item: TokenStream) -> TokenStream {
+	let mut docs = Vec::new();
+	let mut name = None;
+
+	let mut tokens = item.into_iter();
+	while let Some(tree) = tokens.next() {
+		match tree {
+			TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+				let attribute: Vec<_> = group.stream().into_iter().collect();
+
+				if let [TokenTree::Ident(path), TokenTree::Punct(eq), TokenTree::Literal(doc)] = &attribute[..] {
+					if path.to_string() == "doc" && eq.as_char() == '=' {
+						docs.push(doc.to_string());
+					}
+				}
+			},
+
+			TokenTree::Ident(ident) if matches!(ident.to_string().as_str(), "struct" | "enum" | "union") => {
+				name = tokens.next();
+				break;
+			},
+
+			_ => {},
+		}
+	}
+
+	let name = name.expect("expected a type definition");
+	let docs = docs.join(", ");
+	format!("impl {name} {{ const DOCS: &'static [&'static str] = &[{docs}]; }}")
+		.parse()
+		.unwrap()
+}
+
+/// The helper attributes of `#[cfg_attrs]`, which should never remain in its output.
+///
+/// This must be kept in sync with the `HELPERS` of `cfg_attrs` itself, which its unit tests check.
+const HELPERS: &[&str] = &[
+	"cfg_attrs_skip",
+	"configure",
+	"configure_body",
+	"configure_cfg",
+	"configure_cfg_if",
+	"configure_each",
+	"configure_field",
+	"configure_if",
+	"configure_where",
+];
+
+/// Returns the name of the first of the [`HELPERS`] used as an attribute in the given `tokens`,
+/// outside of macro invocations.
+fn leaked_helper(tokens: TokenStream) -> Option<String> {
+	let mut previous: [Option<TokenTree>; 2] = [None, None];
+
+	for tree in tokens {
+		if let TokenTree::Group(group) = &tree {
+			let after_hash = matches!(&previous[1], Some(TokenTree::Punct(punct)) if punct.as_char() == '#')
+				|| matches!(
+					(&previous[0], &previous[1]),
+					(Some(TokenTree::Punct(hash)), Some(TokenTree::Punct(bang)))
+						if hash.as_char() == '#' && bang.as_char() == '!'
+				);
+			let macro_input = matches!(
+				(&previous[0], &previous[1]),
+				(Some(TokenTree::Ident(_)), Some(TokenTree::Punct(bang))) if bang.as_char() == '!'
+			);
+
+			if after_hash && group.delimiter() == Delimiter::Bracket {
+				// Helpers may be qualified as `cfg_attrs::helper`, in which case the name is the fourth
+				// token.
+				let path: Vec<_> = group
+					.stream()
+					.into_iter()
+					.take(4)
+					.map(|tree| tree.to_string())
+					.collect();
+				let name = match &path[..] {
+					[krate, colon, _, name] if krate == "cfg_attrs" && colon == ":" => Some(name),
+					[name, ..] => Some(name),
+					[] => None,
+				};
+
+				if let Some(name) = name.filter(|name| HELPERS.contains(&name.as_str())) {
+					return Some(name.clone());
+				}
+			}
+
+			if !macro_input {
+				if let Some(helper) = leaked_helper(group.stream()) {
+					return Some(helper);
+				}
+			}
+		}
+
+		previous = [previous[1].take(), Some(tree)];
+	}
+
+	None
+}
+
+/// Removes the audit markers emitted with `cfg_attrs`'s `audit` feature from the given `tokens`:
+/// `#[doc(hidden)] const _: (&str, &str) = (...);`.
+fn strip_markers(tokens: TokenStream) -> TokenStream {
+	let trees: Vec<_> = tokens
+		.into_iter()
+		.map(|tree| match tree {
+			TokenTree::Group(group) => {
+				let mut stripped = Group::new(group.delimiter(), strip_markers(group.stream()));
+				stripped.set_span(group.span());
+
+				TokenTree::Group(stripped)
+			},
+
+			tree => tree,
+		})
+		.collect();
+
+	let mut output = Vec::with_capacity(trees.len());
+	let mut index = 0;
+
+	while index < trees.len() {
+		let marker = trees.get(index..(index + 9)).is_some_and(|marker| {
+			let marker: Vec<_> = marker.iter().map(|tree| normalize(tree.clone().into())).collect();
+
+			marker[..7] == ["#", "[ doc ( hidden ) ]", "const", "_", ":", "( & str , & str )", "="] && marker[8] == ";"
+		});
+
+		if marker {
+			index += 9;
+		} else {
+			output.push(trees[index].clone());
+			index += 1;
+		}
+	}
+
+	output.into_iter().collect()
+}
+
+/// Renders the given `tokens` as a string with a single space between each token.
+///
+/// Invisible groups are flattened, so that they don't affect the comparison.
+fn normalize(tokens: TokenStream) -> String {
+	let mut output = String::new();
+
+	for tree in tokens {
+		let token = match tree {
+			TokenTree::Group(group) => {
+				let inner = normalize(group.stream());
+
+				match group.delimiter() {
+					Delimiter::Parenthesis => format!("( {inner} )"),
+					Delimiter::Brace => format!("{{ {inner} }}"),
+					Delimiter::Bracket => format!("[ {inner} ]"),
+					Delimiter::None => inner,
+				}
+			},
+
+			TokenTree::Punct(punct) => punct.as_char().to_string(),
+			other => other.to_string(),
+		};
+
+		if token.is_empty() {
+			continue;
+		}
+
+		if !output.is_empty() {
+			output.push(' ');
+		}
+		output.push_str(&token);
+	}
+
+	output
+}
@@ -0,0 +1,63 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+// A global allocator is registered for the whole test binary, so these tests are kept apart from the
+// others.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+/// Counts its allocations, delegating them to the [`System`] allocator.
+struct Counting(AtomicUsize);
+
+// SAFETY: allocations are delegated to the `System` allocator.
+unsafe impl GlobalAlloc for Counting {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		self.0.fetch_add(1, Ordering::Relaxed);
+
+		// SAFETY: the caller upholds `alloc`'s contract.
+		unsafe { System.alloc(layout) }
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		// SAFETY: the caller upholds `dealloc`'s contract, and `ptr` was allocated by `System`.
+		unsafe { System.dealloc(ptr, layout) }
+	}
+}
+
+// This is within a module so that the `cfg_attr` attribute is seen unexpanded.
+#[cfg_attrs]
+#[expands_to {
+	mod jemalloc {
+		#[cfg_attr(feature = "jemalloc", global_allocator)]
+		static ALLOCATOR: Jemalloc = Jemalloc;
+	}
+}]
+mod jemalloc {
+	#[configure(feature = "jemalloc", #[global_allocator])]
+	static ALLOCATOR: Jemalloc = Jemalloc;
+}
+
+#[cfg_attrs]
+#[configure(all(), #[global_allocator])]
+static REGISTERED: Counting = Counting(AtomicUsize::new(0));
+
+#[cfg_attrs]
+#[configure(any(), #[global_allocator])]
+static UNREGISTERED: Counting = Counting(AtomicUsize::new(0));
+
+#[test]
+fn allocator_is_only_registered_if_the_condition_holds() {
+	let before = REGISTERED.0.load(Ordering::Relaxed);
+	let boxed = Box::new(1);
+
+	assert_eq!(*boxed, 1);
+	assert!(REGISTERED.0.load(Ordering::Relaxed) > before);
+	assert_eq!(UNREGISTERED.0.load(Ordering::Relaxed), 0);
+}
@@ -0,0 +1,360 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::{expands_to, HelperOrder};
+
+#[cfg_attrs]
+#[expands_to {
+	trait NameValue {
+		#[cfg_attr(feature = "x", must_use = "use the result")]
+		fn fallible(&self) -> Result<(), ()>;
+	}
+}]
+trait NameValue {
+	#[configure(feature = "x", #[must_use = "use the result"])]
+	fn fallible(&self) -> Result<(), ()>;
+}
+
+#[cfg_attrs]
+#[derive(HelperOrder)]
+#[helper(a)]
+#[configure(all(), #[helper(b)] #[helper(c)])]
+#[helper(d)]
+#[configure(any(), #[helper(e)])]
+#[helper(f)]
+struct DeriveHelpers;
+
+#[test]
+fn derive_helpers_keep_their_order() {
+	assert_eq!(DeriveHelpers::HELPERS, ["a", "b", "c", "d", "f"]);
+}
+
+// Attributes named `configure` by a path or a raw identifier might belong to another crate, so
+// they aren't rewritten.
+#[cfg_attrs]
+#[expands_to {
+	trait OtherConfigure {
+		#[r#configure(unix, #[inline])]
+		#[other::configure(unix, #[inline])]
+		#[cfg_attr(unix, inline)]
+		fn method(&self);
+	}
+}]
+trait OtherConfigure {
+	#[r#configure(unix, #[inline])]
+	#[other::configure(unix, #[inline])]
+	#[configure(unix, #[inline])]
+	fn method(&self);
+}
+
+/// Provides `HELPERS` for types that don't derive `HelperOrder`, since the inherent constant takes
+/// precedence when it is derived.
+trait NotDerived {
+	const HELPERS: &'static [&'static str] = &["not derived"];
+}
+
+impl<T> NotDerived for T {}
+
+#[cfg_attrs]
+#[derive(Debug)]
+#[configure(all(), #[derive(Clone, HelperOrder)])]
+#[configure(any(), #[derive(Default)])]
+#[derive(PartialEq)]
+#[helper(a)]
+struct ConditionalDerive;
+
+#[cfg_attrs]
+#[derive(Debug)]
+#[configure(any(), #[derive(HelperOrder)])]
+#[configure(any(), #[helper(a)])]
+struct ConditionalDeriveDisabled;
+
+#[test]
+fn conditional_derives_coexist_with_unconditional_ones() {
+	assert_eq!(ConditionalDerive.clone(), ConditionalDerive);
+	assert_eq!(format!("{ConditionalDerive:?}"), "ConditionalDerive");
+	assert_eq!(ConditionalDerive::HELPERS, ["a"]);
+
+	assert_eq!(format!("{ConditionalDeriveDisabled:?}"), "ConditionalDeriveDisabled");
+	assert_eq!(ConditionalDeriveDisabled::HELPERS, ["not derived"]);
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait QualifiedConfigure {
+		#[cfg_attr(unix, inline)]
+		#[cfg_attr(unix, inline)]
+		#[cfg_attr(windows, must_use)]
+		#[cfg_attr(all(not(any(windows)), unix), must_use)]
+		fn method(&self) -> u8;
+	}
+}]
+trait QualifiedConfigure {
+	#[configure(unix, #[inline])]
+	#[cfg_attrs::configure(unix, #[inline])]
+	#[cfg_attrs::configure_if(windows => #[must_use]; unix => #[must_use])]
+	fn method(&self) -> u8;
+}
+
+#[cfg_attrs]
+#[cfg_attrs::configure(all(), #[derive(Clone, Debug, PartialEq)])]
+struct QualifiedOnly;
+
+#[test]
+fn qualified_configure_is_rewritten() {
+	assert_eq!(QualifiedOnly.clone(), QualifiedOnly);
+}
+
+// The helpers that rewrite whole items may be qualified too. This is within a module so that the
+// `cfg` attributes are seen unexpanded.
+#[cfg_attrs]
+#[expands_to {
+	mod qualified_helpers {
+		#[cfg(unix)]
+		#[inline]
+		pub fn cfg() {}
+		#[cfg(not(unix))]
+		pub fn cfg() {}
+
+		/// Emitted verbatim.
+		#[derive(Debug)]
+		pub struct Skipped;
+
+		#[cfg(windows)]
+		pub fn body() -> u8 {
+			1
+		}
+		#[cfg(not(windows))]
+		pub fn body() -> u8 {
+			0
+		}
+	}
+}]
+mod qualified_helpers {
+	#[cfg_attrs::configure_cfg(unix, #[inline])]
+	pub fn cfg() {}
+
+	#[cfg_attrs::cfg_attrs_skip]
+	/// Emitted verbatim.
+	#[derive(Debug)]
+	pub struct Skipped;
+
+	#[cfg_attrs::configure_body(windows, { 1 })]
+	pub fn body() -> u8 {
+		0
+	}
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait MultiSegmentPaths {
+		#[cfg_attr(unix, rustfmt::skip)]
+		#[cfg_attr(unix, clippy::must_use_candidate, ::core::prelude::v1::derive())]
+		#[cfg_attr(unix, mycrate::helper::thing(a::b = "c", d::e::f))]
+		#[cfg_attr(unix, a::b::c::d = "value")]
+		fn method(&self);
+	}
+}]
+trait MultiSegmentPaths {
+	#[configure(unix, #[rustfmt::skip])]
+	#[configure(unix, #[clippy::must_use_candidate] #[::core::prelude::v1::derive()])]
+	#[configure(unix, #[mycrate::helper::thing(a::b = "c", d::e::f)])]
+	#[configure(unix, #[a::b::c::d = "value"])]
+	fn method(&self);
+}
+
+#[cfg_attrs]
+#[configure(all(), #[::core::prelude::v1::derive(::core::clone::Clone, ::core::fmt::Debug, PartialEq)])]
+struct MultiSegmentDerive;
+
+#[test]
+fn multi_segment_paths_are_kept() {
+	assert_eq!(MultiSegmentDerive.clone(), MultiSegmentDerive);
+}
+
+// The item-level attributes are within a module so that their `cfg_attr` attributes are seen
+// unexpanded.
+#[cfg_attrs]
+#[expands_to {
+	mod published {
+		#[cfg_attr(feature = "published", non_exhaustive)]
+		pub struct Config {
+			pub verbose: bool,
+		}
+
+		#[derive(Debug)]
+		#[cfg_attr(feature = "published", non_exhaustive, derive(Clone))]
+		#[cfg_attr(feature = "serde", doc = " Serializable.")]
+		pub enum Event {
+			#[cfg_attr(feature = "published", non_exhaustive)]
+			Started { id: u32, },
+			Stopped,
+		}
+	}
+}]
+mod published {
+	#[configure(feature = "published", #[non_exhaustive])]
+	pub struct Config {
+		pub verbose: bool,
+	}
+
+	#[derive(Debug)]
+	#[configure(feature = "published", #[non_exhaustive] #[derive(Clone)])]
+	#[configure(
+		feature = "serde",
+		/// Serializable.
+	)]
+	pub enum Event {
+		#[configure(feature = "published", #[non_exhaustive])]
+		Started {
+			id: u32,
+		},
+		Stopped,
+	}
+}
+
+#[cfg_attrs]
+#[derive(Clone, Debug, PartialEq)]
+#[configure(all(), #[non_exhaustive])]
+#[configure(any(), #[derive(Default)])]
+enum NonExhaustive {
+	#[configure(all(), #[non_exhaustive])]
+	Variant { id: u32 },
+}
+
+#[test]
+fn non_exhaustive_can_be_configured() {
+	let value = NonExhaustive::Variant { id: 1 };
+	assert_eq!(value.clone(), value);
+}
+
+// Serde's attributes nest lists and key-value pairs deeply, and they're kept exactly as written.
+#[cfg_attrs]
+#[expands_to {
+	mod serde {
+		#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+		#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case", tag = "type", content = "data"))]
+		#[cfg_attr(
+			feature = "serde",
+			serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>")),
+			serde(deny_unknown_fields)
+		)]
+		pub enum Message<T> {
+			#[cfg_attr(feature = "serde", serde(rename = "ping", alias = "p"))]
+			Ping,
+			Data {
+				#[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+				value: Option<T>,
+				#[cfg_attr(feature = "serde", serde(with = "::serde_bytes"))]
+				bytes: Vec<u8>,
+			},
+		}
+	}
+}]
+mod serde {
+	#[configure(feature = "serde", #[derive(Serialize, Deserialize)])]
+	#[configure(feature = "serde", #[serde(rename_all = "kebab-case", tag = "type", content = "data")])]
+	#[configure(
+		feature = "serde",
+		#[serde(bound(serialize = "T: Serialize", deserialize = "T: Deserialize<'de>"))]
+		#[serde(deny_unknown_fields)]
+	)]
+	pub enum Message<T> {
+		#[configure(feature = "serde", #[serde(rename = "ping", alias = "p")])]
+		Ping,
+		Data {
+			#[configure(feature = "serde", #[serde(default, skip_serializing_if = "Option::is_none")])]
+			value: Option<T>,
+			#[configure(feature = "serde", #[serde(with = "::serde_bytes")])]
+			bytes: Vec<u8>,
+		},
+	}
+}
+
+// Conditionally derived impls are still `#[automatically_derived]`, which the dead code lint takes
+// into account: the field is only read by the derived `Debug` impl, so it is still reported as
+// never read. Otherwise, the expectation would be unfulfilled.
+#[cfg_attrs]
+#[configure(all(), #[derive(Debug)])]
+#[configure(any(), #[derive(Clone)])]
+struct DerivedOnly {
+	#[expect(dead_code)]
+	field: u8,
+}
+
+#[test]
+fn conditional_derives_are_automatically_derived() {
+	assert_eq!(format!("{:?}", DerivedOnly { field: 1 }), "DerivedOnly { field: 1 }");
+}
+
+// Derive helpers have to come after the derive that introduces them, and they stay in the order
+// they were written, whether the derive is configured or not.
+#[cfg_attrs]
+#[configure(all(), #[derive(HelperOrder)] #[helper(a)])]
+#[configure(any(), #[helper(b)])]
+#[helper(c)]
+#[configure(all(), #[helper(d)] else #[helper(e)])]
+struct ConfiguredDeriveHelpers;
+
+#[test]
+fn helpers_follow_configured_derives() {
+	assert_eq!(ConfiguredDeriveHelpers::HELPERS, ["a", "c", "d"]);
+}
+
+// Lint attributes with several lints, tool lints, and reasons are kept exactly as written.
+#[cfg_attrs]
+#[expands_to {
+	mod lints {
+		#[cfg_attr(feature = "strict", deny(warnings, clippy::all, clippy::pedantic))]
+		#[cfg_attr(
+			not(feature = "strict"),
+			allow(dead_code, clippy::too_many_arguments, reason = "generated code"),
+			warn(clippy::style, rustdoc::broken_intra_doc_links)
+		)]
+		pub fn configured() {}
+
+		#[cfg_attr(feature = "strict", deny(unused_variables, clippy::needless_return,))]
+		pub fn trailing_comma() {}
+
+		#[cfg_attr(feature = "strict", forbid(unsafe_code, unused_must_use, reason = "audited"))]
+		pub fn forbidden() {}
+	}
+}]
+mod lints {
+	#[configure(
+		feature = "strict",
+		#[deny(warnings, clippy::all, clippy::pedantic)]
+		else
+		#[allow(dead_code, clippy::too_many_arguments, reason = "generated code")]
+		#[warn(clippy::style, rustdoc::broken_intra_doc_links)]
+	)]
+	pub fn configured() {}
+
+	#[configure(feature = "strict", #[deny(unused_variables, clippy::needless_return,)])]
+	pub fn trailing_comma() {}
+
+	#[configure(feature = "strict", #[forbid(unsafe_code, unused_must_use, reason = "audited")])]
+	pub fn forbidden() {}
+}
+
+// If any of the lints were lost, the unused function would be reported when clippy denies warnings.
+#[cfg_attrs]
+#[configure(all(), #[allow(clippy::needless_return, dead_code, reason = "never called")])]
+fn unused() -> u8 {
+	return 1;
+}
+
+#[cfg_attrs]
+#[configure(all(), #[deny(unused_variables, clippy::all, reason = "checked")])]
+fn checked() -> u8 {
+	1
+}
+
+#[test]
+fn configured_lint_levels_apply() {
+	assert_eq!(checked(), 1);
+}
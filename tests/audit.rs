@@ -0,0 +1,48 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "audit")]
+
+use cfg_attrs::cfg_attrs;
+
+#[cfg_attrs]
+#[configure(all(), #[derive(Clone, Copy)])]
+struct Marked {
+	#[configure(
+		any(),
+		/// Hidden.
+		else
+		#[configure_if(unix => #[allow(unused)]; _ => #[allow(dead_code)])]
+	)]
+	field: u8,
+}
+
+#[test]
+fn markers_are_valid_in_blocks() {
+	#[cfg_attrs]
+	#[configure(all(), #[allow(unused)])]
+	fn unused() {}
+
+	let marked = Marked { field: 0 };
+	let _ = (marked, marked);
+}
+
+struct Associated;
+
+impl Associated {
+	#[cfg_attrs]
+	#[configure(all(), #[inline])]
+	pub fn method(&self) -> u8 {
+		1
+	}
+
+	#[cfg_attrs]
+	#[configure(all(), #[doc(hidden)])]
+	pub const CONST: u8 = 2;
+}
+
+#[test]
+fn markers_are_valid_in_associated_items() {
+	assert_eq!(Associated.method() + Associated::CONST, 3);
+}
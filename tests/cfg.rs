@@ -0,0 +1,41 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use cfg_attrs::cfg_attrs;
+
+// `#[configure(a, #[cfg(b)])]` keeps the item only if `a` doesn't hold, or if both `a` and `b`
+// hold: it's removed only when `a && !b`.
+
+#[cfg_attrs]
+#[configure(all(), #[cfg(any())])]
+struct Removed;
+
+// This would conflict with the configured struct if it weren't removed.
+struct Removed(#[allow(dead_code)] u8);
+
+#[cfg_attrs]
+#[configure(all(), #[cfg(all())])]
+struct BothHold;
+
+#[cfg_attrs]
+#[configure(any(), #[cfg(any())])]
+struct ConditionFails;
+
+#[cfg_attrs]
+struct Fields {
+	#[configure(all(), #[cfg(any())])]
+	removed: u8,
+	#[configure(any(), #[cfg(any())])]
+	kept: u8,
+}
+
+#[test]
+fn cfg_composes_with_condition() {
+	let _ = Removed(0);
+	let _ = BothHold;
+	let _ = ConditionFails;
+
+	let Fields { kept } = Fields { kept: 0 };
+	assert_eq!(kept, 0);
+}
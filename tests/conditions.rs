@@ -0,0 +1,359 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	struct BarePaths {
+		#[cfg_attr(unix, a)]
+		unix: u8,
+		#[cfg_attr(test, b)]
+		test: u8,
+		#[cfg_attr(doc, c)]
+		doc: u8,
+		#[cfg_attr(debug_assertions, d, e)]
+		debug_assertions: u8,
+	}
+}]
+struct BarePaths {
+	#[configure(unix, #[a])]
+	unix: u8,
+	#[configure(test, #[b])]
+	test: u8,
+	#[configure(doc, #[c])]
+	doc: u8,
+	#[configure(debug_assertions, #[d] #[e])]
+	debug_assertions: u8,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct Negated {
+		#[cfg_attr(not(feature = "std"), a)]
+		name_value: u8,
+		#[cfg_attr(not(unix), b)]
+		path: u8,
+		#[cfg_attr(not(all(unix, feature = "std")), c)]
+		list: u8,
+	}
+}]
+struct Negated {
+	#[configure(!feature = "std", #[a])]
+	name_value: u8,
+	#[configure(!unix, #[b])]
+	path: u8,
+	#[configure(!all(unix, feature = "std"), #[c])]
+	list: u8,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait Else {
+		#[cfg_attr(debug_assertions, inline(never))]
+		#[cfg_attr(not(debug_assertions), inline(always))]
+		fn tuned() {}
+
+		#[cfg_attr(not(feature = "std"), a, b)]
+		#[cfg_attr(feature = "std", c)]
+		fn negated() {}
+
+		#[cfg_attr(feature = "x", cfg_attr(unix, d), cfg_attr(not(unix), e), f)]
+		fn nested() {}
+
+		#[cfg_attr(not(feature = "x"), g)]
+		fn empty() {}
+	}
+}]
+trait Else {
+	#[configure(debug_assertions, #[inline(never)] else #[inline(always)])]
+	fn tuned() {}
+
+	#[configure(!feature = "std", #[a], #[b], else #[c])]
+	fn negated() {}
+
+	#[configure(feature = "x", #[configure(unix, #[d] else #[e])] #[f])]
+	fn nested() {}
+
+	#[configure(feature = "x", else #[g])]
+	fn empty() {}
+}
+
+#[rustfmt::skip]
+#[cfg_attrs]
+#[expands_to {
+	#[rustfmt::skip]
+	trait ZeroAttributes {
+		fn placeholder();
+
+		fn nested_placeholder();
+
+		#[cfg_attr(all(not(any(unix)), windows), a)]
+		fn placeholder_arm();
+
+		::core::compile_error! {
+			"expected `,` after the condition: `configure(<condition>, <attributes>)` (a trailing comma with no attributes, `configure(<condition>,)`, configures nothing)"
+		}
+		fn missing_comma();
+	}
+}]
+trait ZeroAttributes {
+	#[configure(unix,)]
+	fn placeholder();
+
+	#[configure(unix, #[configure(windows,)])]
+	fn nested_placeholder();
+
+	#[configure_if(unix => ; windows => #[a])]
+	fn placeholder_arm();
+
+	#[configure(unix)]
+	fn missing_comma();
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait NestedErrors {
+		::core::compile_error! { "expected `,` after the condition: `configure(<condition>, <attributes>)` (a trailing comma with no attributes, `configure(<condition>,)`, configures nothing)" }
+		::core::compile_error! { "in this `configure` attribute" }
+		fn one_deep();
+
+		::core::compile_error! { "expected `,` after the condition: `configure(<condition>, <attributes>)` (a trailing comma with no attributes, `configure(<condition>,)`, configures nothing)" }
+		::core::compile_error! { "in this `configure_if` attribute" }
+		fn two_deep();
+
+		::core::compile_error! { "expected `=>`" }
+		fn own_arguments();
+	}
+}]
+trait NestedErrors {
+	#[configure(feature = "x", #[configure(unix)])]
+	fn one_deep();
+
+	#[configure(feature = "x", #[configure_if(unix => #[configure(windows)])])]
+	fn two_deep();
+
+	#[configure_each(unix, #[a])]
+	fn own_arguments();
+}
+
+// A single configured attribute is emitted in `cfg_attr`'s single-attribute form, without a trailing
+// comma, even if one was written after it.
+#[rustfmt::skip]
+#[cfg_attrs]
+#[expands_to {
+	#[rustfmt::skip]
+	trait SingleAttribute {
+		#[cfg_attr(unix, a)]
+		fn single();
+
+		#[cfg_attr(unix, a)]
+		fn trailing_comma();
+
+		#[cfg_attr(unix, a, b)]
+		fn several_trailing_comma();
+
+		#[cfg_attr(unix, a)]
+		#[cfg_attr(not(unix), b)]
+		fn otherwise_trailing_comma();
+	}
+}]
+trait SingleAttribute {
+	#[configure(unix, #[a])]
+	fn single();
+
+	#[configure(unix, #[a],)]
+	fn trailing_comma();
+
+	#[configure(unix, #[a], #[b],)]
+	fn several_trailing_comma();
+
+	#[configure(unix, #[a], else #[b],)]
+	fn otherwise_trailing_comma();
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct ConfigureIf {
+		#[cfg_attr(unix, a)]
+		#[cfg_attr(all(not(any(unix)), windows), b, c)]
+		#[cfg_attr(not(any(unix, windows)), d)]
+		chained: u8,
+		#[cfg_attr(not(feature = "std"), a)]
+		#[cfg_attr(all(not(any(not(feature = "std"))), unix), b)]
+		negated: u8,
+		#[cfg_attr(all(), a)]
+		wildcard: u8,
+		#[cfg_attr(feature = "x", cfg_attr(unix, a), cfg_attr(all(not(any(unix)), windows), b))]
+		nested: u8,
+	}
+}]
+struct ConfigureIf {
+	#[configure_if(
+		unix => #[a];
+		windows => #[b] #[c];
+		_ => #[d];
+	)]
+	chained: u8,
+	#[configure_if(!feature = "std" => #[a]; unix => #[b])]
+	negated: u8,
+	#[configure_if(_ => #[a])]
+	wildcard: u8,
+	#[configure(feature = "x", #[configure_if(unix => #[a]; windows => #[b])])]
+	nested: u8,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait ConfigureIfWildcard {
+		::core::compile_error! { "the `_` arm must be the last arm of `configure_if`" }
+		fn not_last();
+	}
+}]
+trait ConfigureIfWildcard {
+	#[configure_if(_ => #[a]; unix => #[b])]
+	fn not_last();
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct ConfigureEach {
+		#[cfg_attr(any(feature = "a", feature = "b"), derive(Debug))]
+		#[cfg_attr(feature = "a", a, b)]
+		#[cfg_attr(not(feature = "b"), c)]
+		table: u8,
+		#[cfg_attr(unix, a)]
+		single: u8,
+		#[cfg_attr(feature = "x", cfg_attr(unix, a), cfg_attr(unix, b))]
+		nested: u8,
+	}
+}]
+struct ConfigureEach {
+	#[configure_each(
+		any(feature = "a", feature = "b") => #[derive(Debug)];
+		feature = "a" => #[a] #[b];
+		!feature = "b" => #[c];
+	)]
+	table: u8,
+	#[cfg_attrs::configure_each(unix => #[a])]
+	single: u8,
+	#[configure(feature = "x", #[configure_each(unix => #[a]; unix => #[b])])]
+	nested: u8,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait ConfigureEachWildcard {
+		::core::compile_error! { "`configure_each` doesn't support `_` rows: use `all()` for a row that always applies" }
+		fn wildcard();
+	}
+}]
+trait ConfigureEachWildcard {
+	#[configure_each(unix => #[a]; _ => #[b])]
+	fn wildcard();
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct UnusualConditions {
+		#[cfg_attr(version("1.80"), a)]
+		version: u8,
+		#[cfg_attr(accessible(::std::foo::Bar), b)]
+		accessible: u8,
+		#[cfg_attr(not(all(any(version("1.80"), accessible(::std::foo)), target(os = "linux"))), c)]
+		nested: u8,
+		#[cfg_attr(all(target_has_atomic = "64", not(any())), d)]
+		key_value: u8,
+	}
+}]
+struct UnusualConditions {
+	#[configure(version("1.80"), #[a])]
+	version: u8,
+	#[configure(accessible(::std::foo::Bar), #[b])]
+	accessible: u8,
+	#[configure(!all(any(version("1.80"), accessible(::std::foo)), target(os = "linux")), #[c])]
+	nested: u8,
+	#[configure(all(target_has_atomic = "64", not(any())), #[d])]
+	key_value: u8,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct NestedConditions {
+		#[cfg_attr(all(feature = "a", any(unix, windows)), a)]
+		two_deep: u8,
+		#[cfg_attr(all(feature = "a", any(unix, not(target_os = "linux")), not(any(windows, test))), b)]
+		three_deep: u8,
+		#[cfg_attr(any(all(not(any(unix, all(windows, not(target_env = "msvc")))), feature = "a"), test), c, d)]
+		five_deep: u8,
+		#[cfg_attr(not(all(any(unix, windows), not(feature = "a"),)), e)]
+		negated_with_trailing_comma: u8,
+	}
+}]
+struct NestedConditions {
+	#[configure(all(feature = "a", any(unix, windows)), #[a])]
+	two_deep: u8,
+	#[configure(all(feature = "a", any(unix, not(target_os = "linux")), not(any(windows, test))), #[b])]
+	three_deep: u8,
+	#[configure(any(all(not(any(unix, all(windows, not(target_env = "msvc")))), feature = "a"), test), #[c] #[d])]
+	five_deep: u8,
+	#[configure(!all(any(unix, windows), not(feature = "a"),), #[e])]
+	negated_with_trailing_comma: u8,
+}
+
+#[cfg_attrs]
+#[configure(all(any(unix, not(unix)), not(any(not(all()), any()))), #[derive(Clone, Debug, PartialEq)])]
+struct NestedConditionHolds;
+
+#[test]
+fn nested_condition_is_applied() {
+	assert_eq!(NestedConditionHolds.clone(), NestedConditionHolds);
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait FeatureSigils {
+		#[cfg_attr(feature = "std", a)]
+		#[cfg_attr(feature = "my-feature", b)]
+		#[cfg_attr(not(feature = "no-std"), c)]
+		#[cfg_attr(all(feature = "a", not(feature = "b-c"), unix), d)]
+		#[cfg_attr(feature = "type", e)]
+		fn sigils();
+
+		#[cfg_attr(feature = "x", a)]
+		#[cfg_attr(all(not(any(feature = "x")), feature = "y"), b)]
+		#[cfg_attr(all(feature = "z", feature = "w-v"), c, cfg_attr(feature = "u", d))]
+		fn arms();
+
+		::core::compile_error! { "expected a feature name after `$`: `$feature_name`" }
+		fn missing_name();
+	}
+}]
+trait FeatureSigils {
+	#[configure($std, #[a])]
+	#[configure($my_feature, #[b])]
+	#[configure(!$no_std, #[c])]
+	#[configure(all($a, not($b_c), unix), #[d])]
+	#[configure($r#type, #[e])]
+	fn sigils();
+
+	#[configure_if($x => #[a]; $y => #[b])]
+	#[configure(all($z, $w_v), #[c] #[configure($u, #[d])])]
+	fn arms();
+
+	#[configure(any($ "x"), #[a])]
+	fn missing_name();
+}
+
+#[cfg_attrs]
+#[configure(any($full, not($full)), #[derive(Clone, Debug, PartialEq)])]
+struct SigilFeature;
+
+#[test]
+fn feature_sigils_are_conditions() {
+	assert_eq!(SigilFeature.clone(), SigilFeature);
+}
@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[configure_body(all(), { "replaced" })]
+fn enabled() -> &'static str {
+	"original"
+}
+
+#[cfg_attrs]
+#[configure_body(any(), { "replaced" })]
+fn disabled() -> &'static str {
+	"original"
+}
+
+#[cfg_attrs]
+#[configure_body(any(), { "first" })]
+#[configure_body(all(), { "second" })]
+#[configure_body(all(), { "third" })]
+fn first_match() -> &'static str {
+	"original"
+}
+
+#[cfg_attrs]
+/// Other attributes are kept on both copies.
+#[configure_body(!all(), { 0 })]
+#[configure(all(), #[must_use])]
+fn negated() -> u8 {
+	1
+}
+
+#[test]
+fn condition_replaces_body() {
+	assert_eq!(enabled(), "replaced");
+}
+
+#[test]
+fn negation_keeps_body() {
+	assert_eq!(disabled(), "original");
+	assert_eq!(negated(), 1);
+}
+
+#[test]
+fn first_matching_body_applies() {
+	assert_eq!(first_match(), "second");
+}
+
+struct Members;
+
+#[cfg_attrs]
+impl Members {
+	#[configure_body(all(), { "replaced" })]
+	fn method(&self) -> &'static str {
+		"original"
+	}
+
+	#[configure_body(any(), { "first" })]
+	#[configure_body(all(), { "second" })]
+	fn first_match(&self) -> &'static str {
+		"original"
+	}
+}
+
+#[cfg_attrs]
+trait Provided {
+	#[configure_body(all(), { "replaced" })]
+	fn provided(&self) -> &'static str {
+		"original"
+	}
+}
+
+impl Provided for Members {}
+
+#[test]
+fn associated_functions_are_duplicated() {
+	assert_eq!(Members.method(), "replaced");
+	assert_eq!(Members.first_match(), "second");
+	assert_eq!(Members.provided(), "replaced");
+}
+
+// A required trait method has no body to replace. The error is emitted within the trait, and the
+// method is kept as it is.
+#[cfg_attrs]
+#[expands_to {
+	trait Required {
+		::core::compile_error! { "`configure_body` can only be used on functions with bodies" }
+		fn required(&self);
+	}
+}]
+trait Required {
+	#[configure_body(unix, { 0 })]
+	fn required(&self);
+}
@@ -0,0 +1,220 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::{expands_to, returns};
+
+fn assert_copy<T: Copy>() {}
+
+#[cfg_attrs]
+#[configure_cfg(all(), #[derive(Clone, Copy)])]
+struct Enabled;
+
+#[cfg_attrs]
+#[configure_cfg(any(), #[derive(Clone, Copy)])]
+struct Disabled;
+
+// This would conflict with the derived implementation if the attributes were applied.
+impl Clone for Disabled {
+	fn clone(&self) -> Self {
+		Self
+	}
+}
+
+#[cfg_attrs]
+/// Nested `configure` attributes are rewritten in the gated copy.
+#[configure_cfg(
+	all(),
+	#[configure(all(), #[derive(Clone, Copy)])]
+)]
+struct Nested;
+
+#[cfg_attrs]
+#[configure_cfg(all(), #[derive(Clone)])]
+#[configure_cfg(all(), #[derive(Copy)])]
+struct Multiple;
+
+#[test]
+fn condition_applies_attributes() {
+	assert_copy::<Enabled>();
+	assert_copy::<Nested>();
+	assert_copy::<Multiple>();
+}
+
+#[test]
+fn negation_omits_attributes() {
+	let _ = Disabled.clone();
+}
+
+#[cfg_attrs]
+#[configure_cfg(!any(), #[derive(Clone, Copy)])]
+struct NegatedEnabled;
+
+#[cfg_attrs]
+#[configure_cfg(!all(), #[derive(Clone, Copy)])]
+struct NegatedDisabled;
+
+impl Clone for NegatedDisabled {
+	fn clone(&self) -> Self {
+		Self
+	}
+}
+
+#[test]
+fn negated_condition() {
+	assert_copy::<NegatedEnabled>();
+	let _ = NegatedDisabled.clone();
+}
+
+#[cfg_attrs]
+#[configure_cfg(any(), #[derive(Clone)] else #[derive(Clone, Copy)])]
+struct Otherwise;
+
+#[test]
+fn else_applies_attributes_to_negation() {
+	assert_copy::<Otherwise>();
+}
+
+// Associated items are duplicated within their impl or trait.
+#[cfg_attrs]
+#[expands_to {
+	trait Associated {
+		#[cfg(unix)]
+		#[inline]
+		fn method(&self);
+		#[cfg(not(unix))]
+		#[cold]
+		fn method(&self);
+
+		#[cfg(unix)]
+		#[inline]
+		fn chosen(&self);
+		#[cfg(all(not(any(unix)), windows))]
+		#[cold]
+		fn chosen(&self);
+		#[cfg(not(any(unix, windows)))]
+		fn chosen(&self);
+	}
+}]
+trait Associated {
+	#[configure_cfg(unix, #[inline] else #[cold])]
+	fn method(&self);
+
+	#[configure_cfg_if(unix => #[inline]; windows => #[cold])]
+	fn chosen(&self);
+}
+
+struct Members;
+
+#[cfg_attrs]
+impl Members {
+	#[configure_cfg(any(), #[returns(1)] else #[returns(2)])]
+	fn method(&self) -> u8 {
+		0
+	}
+
+	#[configure_cfg_if(any() => #[returns(1)]; _ => #[returns(4)])]
+	fn chosen(&self) -> u8 {
+		0
+	}
+}
+
+#[cfg_attrs]
+trait Provided {
+	#[configure_cfg(all(), #[returns(3)])]
+	fn provided(&self) -> u8 {
+		0
+	}
+}
+
+impl Provided for Members {}
+
+#[test]
+fn associated_items_are_duplicated() {
+	assert_eq!(Members.method(), 2);
+	assert_eq!(Members.provided(), 3);
+	assert_eq!(Members.chosen(), 4);
+}
+
+// The items are within a module so that their `cfg` attributes are seen unexpanded.
+#[cfg_attrs]
+#[expands_to {
+	mod platforms {
+		#[cfg(unix)]
+		#[inline]
+		pub fn separator() -> char {
+			'/'
+		}
+		#[cfg(all(not(any(unix)), windows))]
+		#[inline]
+		#[cold]
+		pub fn separator() -> char {
+			'/'
+		}
+		#[cfg(not(any(unix, windows)))]
+		#[must_use]
+		pub fn separator() -> char {
+			'/'
+		}
+
+		#[cfg(feature = "x")]
+		#[derive(Debug)]
+		pub struct Unmatched;
+		#[cfg(not(any(feature = "x")))]
+		pub struct Unmatched;
+	}
+}]
+mod platforms {
+	#[configure_cfg_if(
+		unix => #[inline];
+		windows => #[inline] #[cold];
+		_ => #[must_use];
+	)]
+	pub fn separator() -> char {
+		'/'
+	}
+
+	#[configure_cfg_if(feature = "x" => #[derive(Debug)])]
+	pub struct Unmatched;
+}
+
+#[cfg_attrs]
+#[expands_to {
+	mod wildcard {
+		::core::compile_error! { "the `_` arm must be the last arm of `configure_cfg_if`" }
+		pub struct NotLast;
+	}
+}]
+mod wildcard {
+	#[configure_cfg_if(_ => #[derive(Debug)]; unix => #[derive(Clone)])]
+	pub struct NotLast;
+}
+
+// Only one copy of each function is compiled, or they would conflict.
+#[cfg_attrs]
+#[configure_cfg_if(any() => #[returns(1)]; all() => #[returns(2)]; _ => #[returns(3)])]
+fn second() -> u8 {
+	0
+}
+
+#[cfg_attrs]
+#[configure_cfg_if(any() => #[returns(1)]; _ => #[returns(2)])]
+fn wildcard() -> u8 {
+	0
+}
+
+#[cfg_attrs]
+#[configure_cfg_if(any() => #[returns(1)]; any() => #[returns(2)])]
+fn unmatched() -> u8 {
+	0
+}
+
+#[test]
+fn exactly_one_arm_applies() {
+	assert_eq!(second(), 2);
+	assert_eq!(wildcard(), 2);
+	assert_eq!(unmatched(), 0);
+}
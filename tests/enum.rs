@@ -0,0 +1,225 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	enum Variants<T>
+	where
+		T: Clone,
+	{
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		Unit,
+		Named {
+			#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+			a: u8,
+			#[cfg_attr(feature = "x", allow(unused))]
+			#[cfg_attr(feature = "y", doc = " Only documented with `y`.")]
+			b: T,
+		},
+		Tuple(
+			#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+			u8,
+			#[cfg_attr(feature = "x", allow(unused))]
+			T,
+		),
+	}
+}]
+enum Variants<T>
+where
+	T: Clone,
+{
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	Unit,
+	Named {
+		#[configure(
+			feature = "x",
+			/// Only documented with `x`.
+		)]
+		a: u8,
+		#[configure(feature = "x", #[allow(unused)])]
+		#[configure(
+			feature = "y",
+			/// Only documented with `y`.
+		)]
+		b: T,
+	},
+	Tuple(
+		#[configure(
+			feature = "x",
+			/// Only documented with `x`.
+		)]
+		u8,
+		#[configure(feature = "x", #[allow(unused)])] T,
+	),
+}
+
+// Struct-like variants are reconstructed by hand, so the degenerate cases are checked separately:
+// trailing commas are kept exactly as written.
+#[rustfmt::skip]
+#[cfg_attrs]
+#[expands_to {
+	#[rustfmt::skip]
+	enum Degenerate {
+		#[cfg_attr(feature = "x", allow(unused))]
+		Empty {},
+		Single {
+			#[cfg_attr(feature = "x", allow(unused))]
+			a: u8
+		},
+		Trailing {
+			#[cfg_attr(feature = "x", allow(unused))]
+			a: u8,
+		},
+		EmptyTuple(),
+		#[cfg_attr(feature = "x", allow(unused))]
+		SingleTuple(#[cfg_attr(feature = "x", allow(unused))] u8)
+	}
+}]
+enum Degenerate {
+	#[configure(feature = "x", #[allow(unused)])]
+	Empty {},
+	Single {
+		#[configure(feature = "x", #[allow(unused)])]
+		a: u8
+	},
+	Trailing {
+		#[configure(feature = "x", #[allow(unused)])]
+		a: u8,
+	},
+	EmptyTuple(),
+	#[configure(feature = "x", #[allow(unused)])]
+	SingleTuple(#[configure(feature = "x", #[allow(unused)])] u8)
+}
+
+#[rustfmt::skip]
+#[cfg_attrs]
+#[derive(Debug, PartialEq)]
+enum Constructed {
+	#[configure(all(), #[allow(unused)])]
+	Empty {},
+	Single {
+		#[configure(all(), #[allow(unused)])]
+		a: u8
+	},
+}
+
+#[test]
+fn degenerate_variants_are_kept() {
+	assert_eq!(Constructed::Empty {}, Constructed::Empty {});
+	assert_ne!(Constructed::Single { a: 1 }, Constructed::Single { a: 2 });
+}
+
+// Modules require the `full` feature.
+#[cfg(feature = "full")]
+#[cfg_attrs(sort)]
+#[expands_to {
+	mod empty {
+		enum Plain {}
+		#[cfg_attr(feature = "x", derive(Debug))]
+		pub enum Configured<T>
+		where
+			T: Copy, {}
+	}
+}]
+mod empty {
+	enum Plain {}
+	#[configure(feature = "x", #[derive(Debug)])]
+	pub enum Configured<T>
+	where
+		T: Copy, {}
+}
+
+#[cfg_attrs]
+#[configure(all(), #[derive(Debug)])]
+enum Never {}
+
+#[test]
+fn empty_enums_are_kept() {
+	fn never(value: Never) -> String {
+		format!("{value:?}")
+	}
+
+	let _: fn(Never) -> String = never;
+}
+
+#[cfg_attrs]
+#[expands_to {
+	enum Discriminants {
+		#[cfg_attr(feature = "x", cfg(feature = "x"))]
+		First = 1,
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		Second = 2,
+		Third = 1 << 2,
+	}
+}]
+enum Discriminants {
+	#[configure(feature = "x", #[cfg(feature = "x")])]
+	First = 1,
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	Second = 2,
+	Third = 1 << 2,
+}
+
+// Variants with fields may also have explicit discriminants, which come after the fields.
+#[cfg_attrs]
+#[expands_to {
+	#[repr(u8)]
+	enum FieldDiscriminants {
+		Unit = 1,
+		Tuple(#[cfg_attr(feature = "x", allow(unused))] u8) = 2,
+		#[cfg_attr(feature = "x", allow(unused))]
+		Named {
+			#[cfg_attr(feature = "x", allow(unused))]
+			a: u8,
+		} = 3,
+	}
+}]
+#[repr(u8)]
+enum FieldDiscriminants {
+	Unit = 1,
+	Tuple(#[configure(feature = "x", #[allow(unused)])] u8) = 2,
+	#[configure(feature = "x", #[allow(unused)])]
+	Named {
+		#[configure(feature = "x", #[allow(unused)])]
+		a: u8,
+	} = 3,
+}
+
+// Gating out a variant leaves the explicit discriminants of the following variants unchanged.
+#[cfg_attrs]
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum Protocol {
+	Hello = 1,
+	#[configure(all(), #[cfg(any())])]
+	Removed = 2,
+	#[configure(any(), #[cfg(any())])]
+	Kept = 3,
+	#[configure(
+		all(),
+		/// The last message.
+	)]
+	Goodbye = 0x10,
+}
+
+#[test]
+fn discriminants_are_kept() {
+	// This match is only exhaustive if `Removed` was gated out.
+	let _ = |protocol: Protocol| match protocol {
+		Protocol::Hello | Protocol::Kept | Protocol::Goodbye => (),
+	};
+
+	assert_eq!(Protocol::Hello as u8, 1);
+	assert_eq!(Protocol::Kept as u8, 3);
+	assert_eq!(Protocol::Goodbye as u8, 0x10);
+}
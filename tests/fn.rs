@@ -0,0 +1,274 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::{expands_to, returns};
+
+// `rustc` expands `#[cfg_attr(...)]` on the item itself before `#[expands_to]` sees it, so this uses
+// a condition that always holds.
+#[cfg_attrs]
+#[expands_to {
+	#[inline]
+	fn foo<T: Default>() -> T
+	where
+		T: Clone,
+	{
+		T::default().clone()
+	}
+}]
+#[configure(all(), #[inline])]
+fn foo<T: Default>() -> T
+where
+	T: Clone,
+{
+	T::default().clone()
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait Methods {
+		#[cfg_attr(feature = "x", inline)]
+		fn foo<'a, T: Default + 'a, const N: usize>(&self) -> [T; N]
+		where
+			T: Clone,
+			Self: Sized,
+		{
+			::core::array::from_fn(|_| T::default().clone())
+		}
+	}
+}]
+trait Methods {
+	#[configure(feature = "x", #[inline])]
+	fn foo<'a, T: Default + 'a, const N: usize>(&self) -> [T; N]
+	where
+		T: Clone,
+		Self: Sized,
+	{
+		::core::array::from_fn(|_| T::default().clone())
+	}
+}
+
+#[cfg_attrs]
+#[configure(all(), #[inline])]
+fn generic<T: Default, U>(value: U) -> (T, U)
+where
+	U: Clone,
+{
+	(T::default(), value.clone())
+}
+
+#[test]
+fn generics_and_where_clause_are_kept() {
+	assert_eq!(generic::<u8, _>(1), (0, 1));
+}
+
+#[cfg_attrs]
+#[expands_to {
+	#[inline]
+	const fn repeat<const N: usize, T: Copy>(value: T) -> [T; N] {
+		[value; N]
+	}
+}]
+#[configure(all(), #[inline])]
+const fn repeat<const N: usize, T: Copy>(value: T) -> [T; N] {
+	[value; N]
+}
+
+// Functions can't have defaults for their const generics, but structs can, and their generics are
+// reconstructed separately from their where clauses.
+#[cfg_attrs]
+#[expands_to {
+	#[derive(Debug)]
+	struct Buffer<T: Copy, const N: usize = 4>([T; N])
+	where
+		T: Default;
+}]
+#[configure(all(), #[derive(Debug)])]
+struct Buffer<T: Copy, const N: usize = 4>([T; N])
+where
+	T: Default;
+
+#[cfg_attrs]
+#[configure_where(any(), T: Copy)]
+fn first<const N: usize, T: Clone>(values: [T; N]) -> Option<T> {
+	values.first().cloned()
+}
+
+#[test]
+fn const_generics_are_kept() {
+	assert_eq!(first([1, 2]), Some(1));
+	assert_eq!(first::<0, u8>([]), None);
+}
+
+// Items and `let` statements at the top level of a function body are processed, but other
+// statements and nested blocks aren't.
+#[cfg_attrs]
+#[expands_to {
+	fn statements() -> u8 {
+		#[cfg_attr(feature = "x", allow(unused_mut))]
+		let mut value = 1;
+
+		#[cfg_attr(feature = "x", derive(Debug))]
+		struct Local;
+
+		#[cfg(feature = "x")]
+		fn helper<T>(value: T) -> T
+		where
+			T: Copy
+		{
+			value
+		}
+		#[cfg(not(feature = "x"))]
+		fn helper<T>(value: T) -> T {
+			value
+		}
+
+		{
+			let nested = 2;
+			value += nested;
+		}
+
+		value
+	}
+}]
+fn statements() -> u8 {
+	#[configure(feature = "x", #[allow(unused_mut)])]
+	let mut value = 1;
+
+	#[configure(feature = "x", #[derive(Debug)])]
+	struct Local;
+
+	#[configure_where(feature = "x", T: Copy)]
+	fn helper<T>(value: T) -> T {
+		value
+	}
+
+	{
+		let nested = 2;
+		value += nested;
+	}
+
+	value
+}
+
+#[cfg_attrs]
+fn configured_let() -> u8 {
+	#[configure(all(), #[allow(unused_mut)])]
+	let mut value = 1;
+
+	#[configure(all(), #[derive(Clone, Copy, Debug, PartialEq)])]
+	struct Local(u8);
+
+	let local = Local(value);
+	value = local.0 + 1;
+	assert_eq!(local.clone(), local);
+
+	value
+}
+
+#[test]
+fn statements_in_bodies_are_configured() {
+	assert_eq!(configured_let(), 2);
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait Parameters {
+		fn method(
+			#[cfg_attr(feature = "x", allow(unused))] &self,
+			#[cfg_attr(feature = "x", allow(unused_variables))] value: u8,
+			#[cfg_attr(unix, allow(unused_variables))]
+			#[cfg_attr(not(unix), allow(unused_mut))]
+			mut other: u8,
+		) -> u8 {
+			0
+		}
+	}
+}]
+trait Parameters {
+	fn method(
+		#[configure(feature = "x", #[allow(unused)])] &self,
+		#[configure(feature = "x", #[allow(unused_variables)])] value: u8,
+		#[configure(unix, #[allow(unused_variables)] else #[allow(unused_mut)])] mut other: u8,
+	) -> u8 {
+		0
+	}
+}
+
+// Parameters can't contain `compile_error!`, so errors are emitted before the item, which is within
+// a module here so that they can be seen.
+#[cfg_attrs]
+#[expands_to {
+	mod parameters {
+		pub struct Parameter;
+
+		::core::compile_error! { "expected attribute arguments in parentheses: `configure(...)`" }
+		impl Parameter {
+			pub fn method(self: &Self, #[cfg_attr(feature = "x", allow(unused_variables))] value: u8, other: u8,) {}
+		}
+	}
+}]
+mod parameters {
+	pub struct Parameter;
+
+	impl Parameter {
+		pub fn method(
+			#[configure] self: &Self,
+			#[configure(feature = "x", #[allow(unused_variables)])] value: u8,
+			other: u8,
+		) {
+		}
+	}
+}
+
+#[cfg_attrs]
+fn sum(#[configure(all(), #[allow(unused_mut)])] mut a: u8, b: u8) -> u8 {
+	a + b
+}
+
+#[test]
+fn parameters_are_configured() {
+	assert_eq!(sum(1, 2), 3);
+}
+
+// Configured attribute macros are applied by `rustc` only if the condition holds.
+#[cfg_attrs]
+#[configure(all(), #[returns(2)])]
+fn enabled() -> u8 {
+	1
+}
+
+#[cfg_attrs]
+#[configure(any(), #[returns(2)])]
+fn disabled() -> u8 {
+	1
+}
+
+#[cfg_attrs]
+#[configure(any(), #[returns(2)] else #[cfg_attrs_test_macros::returns(3)])]
+fn otherwise() -> u8 {
+	1
+}
+
+trait Returns {
+	fn value(&self) -> u8;
+}
+
+#[cfg_attrs]
+impl Returns for u8 {
+	#[configure_if(any() => #[returns(2)]; all() => #[returns(*self + 1)])]
+	fn value(&self) -> u8 {
+		0
+	}
+}
+
+#[test]
+fn configured_attribute_macros_are_applied() {
+	assert_eq!(enabled(), 2);
+	assert_eq!(disabled(), 1);
+	assert_eq!(otherwise(), 3);
+	assert_eq!(4u8.value(), 5);
+}
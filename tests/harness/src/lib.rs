@@ -0,0 +1,27 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg_attr(feature = "bench", feature(test))]
+
+#[cfg(feature = "bench")]
+extern crate test;
+
+use cfg_attrs::cfg_attrs;
+#[cfg(feature = "bench")]
+use test::Bencher;
+
+/// Stands in for `test::Bencher`, which is only available on nightly.
+#[cfg(not(feature = "bench"))]
+struct Bencher;
+
+#[cfg_attrs]
+#[configure(feature = "slow-tests", #[test] else #[allow(dead_code)])]
+fn slow() {}
+
+#[cfg_attrs]
+#[configure(feature = "bench", #[bench] else #[allow(dead_code)])]
+fn benchmark(_bencher: &mut Bencher) {}
+
+#[test]
+fn always() {}
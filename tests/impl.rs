@@ -0,0 +1,229 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	impl<T: Clone> Wrapper<T>
+	where
+		T: Default,
+	{
+		#![allow(unused)]
+
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		pub const CONST: u8 = 0;
+
+		#[cfg_attr(feature = "x", inline)]
+		pub fn method(&self) -> T {
+			self.0.clone()
+		}
+	}
+}]
+impl<T: Clone> Wrapper<T>
+where
+	T: Default,
+{
+	#![configure(all(), #[allow(unused)])]
+
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	pub const CONST: u8 = 0;
+
+	#[configure(feature = "x", #[inline])]
+	pub fn method(&self) -> T {
+		self.0.clone()
+	}
+}
+
+#[cfg_attrs]
+#[expands_to {
+	#[allow(unused)]
+	unsafe impl<T> Sync for Wrapper<T> {}
+}]
+#[configure(all(), #[allow(unused)])]
+unsafe impl<T> Sync for Wrapper<T> {}
+
+#[cfg_attrs]
+#[expands_to {
+	impl<T> ::core::ops::Deref for Wrapper<T> {
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		type Target = T;
+
+		fn deref(&self) -> &T {
+			&self.0
+		}
+	}
+}]
+impl<T> ::core::ops::Deref for Wrapper<T> {
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	type Target = T;
+
+	fn deref(&self) -> &T {
+		&self.0
+	}
+}
+
+struct Wrapper<T>(T);
+
+struct Removed(Cell<u8>);
+
+// A configured `#[cfg(...)]` attribute only removes the item if the `configure` condition holds and
+// the `cfg` condition doesn't.
+#[cfg_attrs]
+#[configure(all(), #[cfg(any())])]
+unsafe impl Sync for Removed {}
+
+struct Kept(Cell<u8>);
+
+#[cfg_attrs]
+#[configure(any(), #[cfg(any())])]
+unsafe impl Sync for Kept {}
+
+/// `IsSync::<T>::VALUE` is `true` only if `T` implements `Sync`, since the inherent constant takes
+/// precedence when its bound is met.
+struct IsSync<T>(PhantomData<T>);
+
+impl<T: Sync> IsSync<T> {
+	const VALUE: bool = true;
+}
+
+trait NotSyncFallback {
+	const VALUE: bool = false;
+}
+
+impl<T> NotSyncFallback for IsSync<T> {}
+
+const _: () = assert!(!IsSync::<Removed>::VALUE);
+const _: () = assert!(IsSync::<Kept>::VALUE);
+
+#[test]
+fn configured_unsafe_impl_is_gated() {
+	assert_eq!(Removed(Cell::new(1)).0.get(), 1);
+	assert_eq!(Kept(Cell::new(1)).0.get(), 1);
+	assert_eq!(Wrapper(1).0, 1);
+}
+
+#[cfg_attrs]
+#[expands_to {
+	#[allow(clippy::implicit_hasher)]
+	impl<'a, K, V: 'a, const N: usize> Wrapper<[::std::vec::Vec<::std::collections::HashMap<K, &'a V>>; N]>
+	where
+		K: ::core::hash::Hash + Eq,
+		for<'b> &'b V: Into<u8>,
+	{
+		#[cfg_attr(feature = "x", inline)]
+		fn len(&self) -> usize {
+			self.0.len()
+		}
+	}
+}]
+#[configure(all(), #[allow(clippy::implicit_hasher)])]
+impl<'a, K, V: 'a, const N: usize> Wrapper<[::std::vec::Vec<::std::collections::HashMap<K, &'a V>>; N]>
+where
+	K: ::core::hash::Hash + Eq,
+	for<'b> &'b V: Into<u8>,
+{
+	#[configure(feature = "x", #[inline])]
+	fn len(&self) -> usize {
+		self.0.len()
+	}
+}
+
+#[cfg_attrs]
+#[configure(all(), #[allow(clippy::type_complexity)])]
+impl<T: Clone> From<&(Wrapper<Option<Box<dyn Fn(&T) -> T>>>, T)> for Wrapper<Vec<T>> {
+	fn from((_, value): &(Wrapper<Option<Box<dyn Fn(&T) -> T>>>, T)) -> Self {
+		Wrapper(vec![value.clone()])
+	}
+}
+
+#[test]
+fn complex_self_types_are_kept() {
+	let Wrapper(values) = Wrapper::<Vec<u8>>::from(&(Wrapper(None), 1));
+	assert_eq!(values, [1]);
+}
+
+/// A trait with a generic associated type, for impls of it to define.
+trait Container {
+	type Item<T>
+	where
+		T: Copy;
+
+	fn first<T: Copy>(values: Self::Item<T>) -> Option<T>;
+}
+
+#[cfg_attrs]
+#[expands_to {
+	impl Container for Wrapper<()> {
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		#[cfg_attr(feature = "y", allow(unused))]
+		type Item<T> = Vec<T>
+		where
+			T: Copy;
+
+		fn first<T: Copy>(values: Self::Item<T>) -> Option<T> {
+			values.first().copied()
+		}
+	}
+}]
+impl Container for Wrapper<()> {
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	#[configure(feature = "y", #[allow(unused)])]
+	type Item<T>
+		= Vec<T>
+	where
+		T: Copy;
+
+	fn first<T: Copy>(values: Self::Item<T>) -> Option<T> {
+		values.first().copied()
+	}
+}
+
+#[cfg_attrs]
+impl Iterator for Wrapper<u8> {
+	#[configure(
+		all(),
+		/// Counts down to zero.
+	)]
+	type Item = u8;
+
+	fn next(&mut self) -> Option<u8> {
+		self.0 = self.0.checked_sub(1)?;
+		Some(self.0)
+	}
+}
+
+#[cfg_attrs]
+impl Container for Wrapper<u8> {
+	#[configure(all(), #[allow(unused)])]
+	type Item<T>
+		= [T; 1]
+	where
+		T: Copy;
+
+	fn first<T: Copy>([value]: Self::Item<T>) -> Option<T> {
+		Some(value)
+	}
+}
+
+#[test]
+fn associated_types_are_configured() {
+	assert_eq!(Wrapper(3u8).collect::<Vec<_>>(), [2, 1, 0]);
+	assert_eq!(<Wrapper<u8> as Container>::first([1]), Some(1));
+}
@@ -0,0 +1,122 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::{cfg_attrs, cfg_attrs_items};
+use cfg_attrs_test_macros::expands_to;
+
+// The input of a macro invocation is opaque, so any `configure` attributes within it are left as
+// they are.
+#[cfg_attrs]
+#[expands_to {
+	make_struct! {
+		struct Opaque {
+			#[configure(all(), /// Docs.
+			)]
+			field: u8,
+		}
+	}
+}]
+make_struct! {
+	struct Opaque {
+		#[configure(all(), /// Docs.
+		)]
+		field: u8,
+	}
+}
+
+/// Generates a struct with the given fields, placing `#[cfg_attrs]` on the generated struct so that
+/// configured fields are rewritten.
+macro_rules! make_struct {
+	($(#[$attr:meta])* struct $name:ident { $($fields:tt)* }) => {
+		#[cfg_attrs]
+		$(#[$attr])*
+		struct $name { $($fields)* }
+	};
+}
+
+make_struct! {
+	#[derive(Default)]
+	struct Generated {
+		#[configure(all(), #[allow(dead_code)])]
+		field: u8,
+	}
+}
+
+#[test]
+fn generated_fields_are_configured() {
+	let _ = Generated::default();
+}
+
+#[cfg_attrs]
+#[expands_to {
+	#[macro_export]
+	macro_rules! exported {
+		() => {};
+		($value:expr) => { $value };
+	}
+}]
+#[configure(all(), #[macro_export])]
+macro_rules! exported {
+	() => {};
+	($value:expr) => {
+		$value
+	};
+}
+
+#[cfg_attrs]
+#[configure(all(), #[macro_export])]
+macro_rules! double {
+	($value:expr) => {
+		$value * 2
+	};
+}
+
+#[test]
+fn configured_macro_rules_are_kept() {
+	assert_eq!(double!(2), 4);
+}
+
+/// Generates a unit struct for each of the given names, processing them all with
+/// `cfg_attrs_items!`, since the generated items have no single item to mark with `#[cfg_attrs]`.
+macro_rules! make_units {
+	($($name:ident),*) => {
+		cfg_attrs_items! {
+			$(
+				#[configure(all(), #[derive(Clone, Debug, PartialEq)])]
+				#[configure(any(), #[derive(Default)])]
+				struct $name;
+			)*
+		}
+	};
+}
+
+make_units!(First, Second);
+
+#[test]
+fn generated_items_are_configured() {
+	assert_eq!(First.clone(), First);
+	assert_eq!(format!("{Second:?}"), "Second");
+}
+
+// Each item is processed like one marked with `#[cfg_attrs]`, including its nested items.
+cfg_attrs_items! {
+	#[configure_cfg(all(), #[derive(Default)])]
+	struct Duplicated(u8);
+
+	mod nested {
+		#[configure(all(), #[derive(Debug)])]
+		pub struct Inner {
+			#[configure(all(), #[allow(dead_code)])]
+			pub field: u8,
+		}
+	}
+}
+
+#[test]
+fn items_are_processed_like_attribute_items() {
+	assert_eq!(Duplicated::default().0, 0);
+	assert_eq!(format!("{:?}", nested::Inner { field: 1 }), "Inner { field: 1 }");
+}
@@ -0,0 +1,115 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+// `rustc` expands `#[cfg_attr(...)]` on the module itself, including its inner attributes, before
+// `#[expands_to]` sees it, so the module's own `configure` attributes use conditions that always
+// hold.
+#[cfg_attrs]
+#[expands_to {
+	#[doc = " Module docs."]
+	mod configured {
+		#![allow(unused)]
+
+		#[cfg_attr(feature = "x", doc = " Struct docs.")]
+		struct Inner {
+			#[cfg_attr(unix, doc = " Field docs.")]
+			field: u8,
+		}
+
+		mod nested {
+			#[cfg_attr(windows, inline)]
+			fn inner() {
+				#![cfg_attr(feature = "x", allow(unused_variables))]
+				#![allow(unused)]
+
+				let unused = 0;
+			}
+		}
+
+	}
+}]
+#[configure(all(), /// Module docs.
+)]
+mod configured {
+	#![configure(all(), #[allow(unused)])]
+
+	#[configure(feature = "x", /// Struct docs.
+	)]
+	struct Inner {
+		#[configure(unix, /// Field docs.
+		)]
+		field: u8,
+	}
+
+	mod nested {
+		#[configure(windows, #[inline])]
+		fn inner() {
+			#![configure(feature = "x", #[allow(unused_variables)])]
+			#![allow(unused)]
+
+			let unused = 0;
+		}
+	}
+}
+
+fn assert_copy<T: Copy>() {}
+
+#[cfg_attrs]
+#[configure(all(), #[cfg(all())])]
+mod gated {
+	#[configure(all(), #[derive(Clone, Copy)])]
+	pub struct Copied;
+
+	#[configure(any(), #[derive(Clone, Copy)])]
+	pub struct NotCopied;
+
+	impl Clone for NotCopied {
+		fn clone(&self) -> Self {
+			Self
+		}
+	}
+}
+
+#[cfg_attrs]
+#[configure(all(), #[must_use])]
+fn inner_attributes() -> u8 {
+	#![allow(unused_variables)]
+
+	let unused = 0;
+	1
+}
+
+#[test]
+fn module_and_contents_are_configured() {
+	assert_copy::<gated::Copied>();
+	let _ = gated::NotCopied.clone();
+
+	assert_eq!(inner_attributes(), 1);
+}
+
+// Extern blocks aren't processed, so helpers on them are an error rather than being left in place.
+#[cfg_attrs]
+#[expands_to {
+	mod unsupported {
+		::core::compile_error! {
+			"`#[cfg_attrs]` doesn't support this kind of item yet, so its helper attributes can't be used on or within it"
+		}
+		#[allow(unused)]
+		extern "C" {
+			fn function();
+		}
+	}
+}]
+mod unsupported {
+	#[configure(unix, #[allow(clashing_extern_declarations)])]
+	#[allow(unused)]
+	extern "C" {
+		fn function();
+	}
+}
@@ -0,0 +1,464 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs(recursion_limit = 2)]
+#[expands_to {
+	trait RecursionLimit {
+		#[cfg_attr(a, cfg_attr(b, c))]
+		fn within_limit();
+
+		::core::compile_error! {
+			"`configure` attributes are nested too deeply (the limit is 2, which can be raised with `#[cfg_attrs(recursion_limit = ...)]`)"
+		}
+		fn beyond_limit();
+	}
+}]
+trait RecursionLimit {
+	#[configure(a, #[configure(b, #[c])])]
+	fn within_limit();
+
+	#[configure(a, #[configure(b, #[configure(c, #[d])])])]
+	fn beyond_limit();
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct DefaultRecursionLimit {
+		#[cfg_attr(a, cfg_attr(b, cfg_attr(c, cfg_attr(d, cfg_attr(e, cfg_attr(f, cfg_attr(g, cfg_attr(h, i))))))))]
+		field: u8,
+	}
+}]
+struct DefaultRecursionLimit {
+	#[configure(a, #[configure(b, #[configure(c, #[configure(d, #[configure(e, #[configure(f, #[configure(g, #[configure(h, #[i])])])])])])])])]
+	field: u8,
+}
+
+// Duplicates are only warned about with `warn_duplicates`.
+#[cfg_attrs]
+#[expands_to {
+	struct Duplicates {
+		#[allow(dead_code)]
+		#[cfg_attr(all(), allow(dead_code))]
+		field: u8,
+	}
+}]
+struct Duplicates {
+	#[allow(dead_code)]
+	#[configure(all(), #[allow(dead_code)])]
+	field: u8,
+}
+
+// The duplicate is still warned about when `#[cfg_attrs]` is on an associated function or constant,
+// since the warning is emitted within its body or value rather than before it.
+struct Warned;
+
+#[expect(deprecated)]
+impl Warned {
+	#[cfg_attrs(warn_duplicates)]
+	#[allow(unused)]
+	#[configure(all(), #[allow(unused)])]
+	fn method(&self) -> u8 {
+		1
+	}
+
+	#[cfg_attrs(warn_duplicates)]
+	#[allow(unused)]
+	#[configure(all(), #[allow(unused)])]
+	const CONST: u8 = 2;
+
+	// Those for statements go before the statement, within the body.
+	#[cfg_attrs(warn_duplicates)]
+	fn statement(&self) -> u8 {
+		#[allow(unused)]
+		#[configure(all(), #[allow(unused)])]
+		let value = 4;
+
+		value
+	}
+}
+
+#[test]
+fn duplicates_are_warned_about_in_associated_items() {
+	assert_eq!(Warned.method() + Warned::CONST + Warned.statement(), 7);
+}
+
+#[cfg_attrs(deny_unknown)]
+#[expands_to {
+	trait DenyUnknown {
+		#[cfg_attr(unix, a)]
+		#[cfg_attrs]
+		#[config]
+		fn known();
+
+		::core::compile_error! {
+			"unknown `cfg_attrs` helper attribute: `configure_iff`"
+		}
+		fn misspelled();
+
+		::core::compile_error! {
+			"unknown `cfg_attrs` helper attribute: `cfg_attrs_skp`"
+		}
+		::core::compile_error! { "in this `configure` attribute" }
+		fn nested();
+	}
+}]
+trait DenyUnknown {
+	#[configure_if(unix => #[a])]
+	#[cfg_attrs]
+	#[config]
+	fn known();
+
+	#[configure_iff(unix => #[a])]
+	fn misspelled();
+
+	#[configure(unix, #[cfg_attrs_skp])]
+	fn nested();
+}
+
+#[cfg_attrs(apply_to_members(unix, #[allow(unused)]), apply_to_members(windows, #[inline]))]
+#[expands_to {
+	trait ApplyToMembers {
+		#[cfg_attr(unix, allow(unused))]
+		#[cfg_attr(windows, inline)]
+		const CONST: u8;
+
+		#[cfg_attr(unix, allow(unused))]
+		#[cfg_attr(windows, inline)]
+		#[cfg_attr(feature = "x", must_use)]
+		fn method(&self) -> u8;
+	}
+}]
+trait ApplyToMembers {
+	const CONST: u8;
+
+	#[configure(feature = "x", #[must_use])]
+	fn method(&self) -> u8;
+}
+
+#[cfg_attrs(apply_to_fields(feature = "serde", #[serde(default)]), apply_to_fields(unix, #[allow(unused)]))]
+#[expands_to {
+	struct ApplyToFields {
+		#[cfg_attr(feature = "serde", serde(default))]
+		#[cfg_attr(unix, allow(unused))]
+		plain: u8,
+		#[cfg_attr(unix, allow(unused))]
+		#[serde(skip)]
+		written: u8,
+		#[cfg_attr(unix, allow(unused))]
+		#[cfg_attr(feature = "x", serde(rename = "x"))]
+		configured: u8,
+		#[cfg_attr(feature = "serde", serde(default))]
+		#[cfg_attr(not(unix), allow(dead_code))]
+		chained: u8,
+	}
+}]
+struct ApplyToFields {
+	plain: u8,
+	#[serde(skip)]
+	written: u8,
+	#[configure(feature = "x", #[serde(rename = "x")])]
+	configured: u8,
+	#[configure_if(!unix => #[allow(dead_code)])]
+	chained: u8,
+}
+
+// Fields added by `configure_field` are included, and the fields of tuple structs have their
+// attributes applied before the field too.
+#[rustfmt::skip]
+#[cfg_attrs(apply_to_fields(feature = "serde", #[serde(default)]))]
+#[expands_to {
+	#[rustfmt::skip]
+	struct Tuple(
+		#[cfg_attr(feature = "serde", serde(default))] u8,
+		#[cfg_attr(feature = "serde", serde(default))]
+		#[cfg(feature = "x")]
+		u16
+	);
+}]
+#[configure_field(feature = "x", u16)]
+struct Tuple(u8);
+
+#[cfg_attrs(apply_to_fields(all(), #[allow(dead_code)]))]
+struct AppliedFields {
+	unread: u8,
+	also_unread: (u8, u8),
+}
+
+#[test]
+fn apply_to_fields_applies_to_every_field() {
+	let _ = AppliedFields {
+		unread: 1,
+		also_unread: (2, 3),
+	};
+}
+
+#[cfg_attrs(sort)]
+#[expands_to {
+	struct Sort {
+		#[cfg_attr(a, allow(unused))]
+		#[cfg_attr(b, allow(unused))]
+		#[cfg_attr(c, allow(unused))]
+		#[allow(dead_code)]
+		#[cfg_attr(z, doc = " Docs.")]
+		#[cfg_attr(b, deprecated)]
+		#[cfg_attr(y, doc = " More docs.")]
+		#[cfg_attr(a, deprecated)]
+		field: u8,
+	}
+}]
+struct Sort {
+	#[configure(c, #[allow(unused)])]
+	#[configure(a, #[allow(unused)])]
+	#[configure(b, #[allow(unused)])]
+	#[allow(dead_code)]
+	#[configure(z, #[doc = " Docs."])]
+	#[configure(b, #[deprecated])]
+	#[configure(y, #[doc = " More docs."])]
+	#[configure(a, #[deprecated])]
+	field: u8,
+}
+
+#[cfg_attrs(strip)]
+#[expands_to {
+	trait Strip {
+		#[allow(unused)]
+		const CONST: u8;
+
+		fn method<T>(&self)
+		where
+			T: Copy;
+
+		fn provided(&self) {}
+	}
+}]
+trait Strip {
+	#[configure(unix, #[inline])]
+	#[allow(unused)]
+	#[configure_if(unix => #[must_use]; _ => #[inline])]
+	const CONST: u8;
+
+	#[configure_where(feature = "x", T: Clone)]
+	fn method<T>(&self)
+	where
+		T: Copy;
+
+	#[configure_body(feature = "x", {
+		unimplemented!()
+	})]
+	fn provided(&self) {}
+}
+
+#[cfg_attrs(strip)]
+#[configure_field(feature = "x", extra: u8)]
+#[configure(all(), #[derive(Debug)])]
+struct StripFields {
+	field: u8,
+}
+
+// Stripped attributes aren't warned about as duplicates, since they aren't emitted at all.
+#[deny(deprecated)]
+mod strip_duplicates {
+	use super::*;
+
+	#[cfg_attrs(strip, warn_duplicates)]
+	#[derive(Debug)]
+	#[configure(all(), #[derive(Debug)])]
+	pub struct StripDuplicates;
+}
+
+#[test]
+fn strip_removes_item_helpers() {
+	let StripFields { field } = StripFields { field: 1 };
+	assert_eq!(field, 1);
+	assert_eq!(format!("{:?}", strip_duplicates::StripDuplicates), "StripDuplicates");
+}
+
+#[cfg_attrs(apply_to_members(feature = "tracing", #[tracing::instrument]))]
+#[expands_to {
+	trait ApplyToMembersComposition {
+		#[cfg_attr(feature = "tracing", tracing::instrument)]
+		#[cfg(feature = "x")]
+		#[cfg_attr(feature = "y", inline)]
+		fn duplicated(&self)
+		where
+			Self: Sized;
+		#[cfg_attr(feature = "tracing", tracing::instrument)]
+		#[cfg(not(feature = "x"))]
+		#[cfg_attr(feature = "y", inline)]
+		fn duplicated(&self);
+
+		#[cfg_attr(feature = "tracing", tracing::instrument)]
+		#[cfg_attr(unix, must_use)]
+		#[cfg_attr(all(not(any(unix)), windows), inline)]
+		fn chained(&self) -> u8;
+	}
+}]
+trait ApplyToMembersComposition {
+	#[configure_where(feature = "x", Self: Sized)]
+	#[configure(feature = "y", #[inline])]
+	fn duplicated(&self);
+
+	#[configure_if(unix => #[must_use]; windows => #[inline])]
+	fn chained(&self) -> u8;
+}
+
+#[cfg_attrs(mixed_site)]
+#[expands_to {
+	struct MixedSite {
+		#[allow(dead_code)]
+		#[cfg_attr(unix, allow(unused))]
+		#[cfg_attr(not(unix), allow(unused))]
+		field: u8,
+	}
+}]
+struct MixedSite {
+	#[allow(dead_code)]
+	#[configure(unix, #[allow(unused)] else #[allow(unused)])]
+	field: u8,
+}
+
+#[cfg_attrs(mixed_site)]
+#[configure(all(), #[derive(Clone, Debug, PartialEq)])]
+struct MixedSiteDerive(u8);
+
+#[test]
+fn mixed_site_attributes_apply() {
+	assert_eq!(MixedSiteDerive(1).clone(), MixedSiteDerive(1));
+}
+
+// The two strategies are compared within modules, so that `#[expands_to]` sees the `#[cfg(...)]`
+// attributes.
+#[cfg_attrs]
+#[expands_to {
+	mod cfg_attr_strategy {
+		#[cfg_attr(unix, cfg(feature = "x"))]
+		fn gated() {}
+	}
+}]
+mod cfg_attr_strategy {
+	#[configure(unix, #[cfg(feature = "x")])]
+	fn gated() {}
+}
+
+#[cfg_attrs(split_cfg)]
+#[expands_to {
+	mod split_cfg_strategy {
+		#[cfg(unix)]
+		#[cfg(feature = "x")]
+		fn gated() {}
+		#[cfg(not(unix))]
+		fn gated() {}
+
+		#[cfg_attr(unix, cfg(feature = "x"), inline)]
+		fn mixed() {}
+
+		#[cfg(windows)]
+		#[cfg(feature = "x")]
+		fn both() {}
+		#[cfg(not(windows))]
+		#[cfg(feature = "y")]
+		fn both() {}
+	}
+}]
+mod split_cfg_strategy {
+	#[configure(unix, #[cfg(feature = "x")])]
+	fn gated() {}
+
+	#[configure(unix, #[cfg(feature = "x")] #[inline])]
+	fn mixed() {}
+
+	#[configure(windows, #[cfg(feature = "x")] else #[cfg(feature = "y")])]
+	fn both() {}
+}
+
+#[cfg_attrs(split_cfg)]
+#[configure(all(), #[cfg(all())])]
+fn split_kept() -> u8 {
+	1
+}
+
+#[cfg_attrs(split_cfg)]
+#[configure(any(), #[cfg(any())])]
+fn split_kept_otherwise() -> u8 {
+	2
+}
+
+#[test]
+fn split_cfg_keeps_items() {
+	assert_eq!(split_kept(), 1);
+	assert_eq!(split_kept_otherwise(), 2);
+}
+
+#[cfg_attrs(features_only)]
+#[expands_to {
+	trait FeaturesOnly {
+		#[cfg_attr(feature = "std", a)]
+		#[cfg_attr(not(feature = "alloc"), b)]
+		#[cfg_attr(all(feature = "serde", any(feature = "json", not(feature = "no_yaml"))), c)]
+		#[cfg_attr(all(target_os = "linux", feature = "x"), d)]
+		#[cfg_attr(feature = "kebab-case", e)]
+		#[cfg_attr(target::os, f)]
+		#[cfg_attr(version("1.80"), g)]
+		fn bare();
+
+		#[cfg_attr(feature = "a", a)]
+		#[cfg_attr(all(not(any(feature = "a")), feature = "b"), b)]
+		#[cfg_attr(feature = "c", c)]
+		fn arms();
+
+		#[cfg_attr(debug_assertions, a)]
+		#[cfg_attr(all(unix, not(test), feature = "std"), b)]
+		#[cfg_attr(any(tokio_unstable, doc), c)]
+		fn well_known();
+
+		#[cfg(feature = "d")]
+		fn duplicated()
+		where
+			Self: Sized;
+		#[cfg(not(feature = "d"))]
+		fn duplicated();
+	}
+}]
+trait FeaturesOnly {
+	#[configure(std, #[a])]
+	#[configure(!alloc, #[b])]
+	#[configure(all(serde, any(json, not(no_yaml))), #[c])]
+	#[configure(all(target_os = "linux", feature = "x"), #[d])]
+	#[configure($kebab_case, #[e])]
+	#[configure(target::os, #[f])]
+	#[configure(version("1.80"), #[g])]
+	fn bare();
+
+	#[configure_if(a => #[a]; b => #[b])]
+	#[configure_each(c => #[c])]
+	fn arms();
+
+	#[configure(debug_assertions, #[a])]
+	#[configure(all(unix, not(test), std), #[b])]
+	#[configure(any(r#tokio_unstable, doc), #[c])]
+	fn well_known();
+
+	#[configure_where(d, Self: Sized)]
+	fn duplicated();
+}
+
+#[cfg_attrs(features_only)]
+#[configure(any(full, not(full)), #[derive(Clone, Debug, PartialEq)])]
+struct FeaturesOnlyDerive;
+
+#[cfg_attrs(features_only)]
+#[configure(debug_assertions, #[derive(Clone, Debug, PartialEq)])]
+#[configure(not(debug_assertions), #[derive(Clone, Debug, PartialEq)])]
+struct DebugAssertionsDerive;
+
+#[test]
+fn features_only_conditions_are_features() {
+	assert_eq!(FeaturesOnlyDerive.clone(), FeaturesOnlyDerive);
+	assert_eq!(DebugAssertionsDerive.clone(), DebugAssertionsDerive);
+}
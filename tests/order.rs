@@ -0,0 +1,202 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::{expands_to, processed, DocOrder, HelperOrder};
+
+#[cfg_attrs]
+#[expands_to {
+	struct SourceOrder {
+		/// First.
+		#[cfg_attr(feature = "a", doc = " Second.", doc = " Third.")]
+		/// Fourth.
+		#[cfg_attr(feature = "b", doc = " Fifth.")]
+		#[cfg_attr(feature = "a", doc = " Sixth.")]
+		field: u8,
+	}
+}]
+struct SourceOrder {
+	/// First.
+	#[configure(
+		feature = "a",
+		/// Second.
+		/// Third.
+	)]
+	/// Fourth.
+	#[configure(
+		feature = "b",
+		/// Fifth.
+	)]
+	#[configure(
+		feature = "a",
+		/// Sixth.
+	)]
+	field: u8,
+}
+
+// Expanding the output of `#[cfg_attrs]` again leaves it unchanged. A helper within a macro
+// invocation isn't rewritten, so the second expansion still sees a `configure` attribute and can't
+// emit the item as it was written without reconstructing it. Modules require the `full` feature.
+#[cfg(feature = "full")]
+#[cfg_attrs]
+#[cfg_attrs]
+#[expands_to {
+	mod idempotent {
+		pub struct Idempotent {
+			#[cfg_attr(not(unix), a, cfg_attr(feature = "x", doc = " Docs."))]
+			#[cfg_attr(unix, b)]
+			#[cfg_attr(windows, c)]
+			pub field: u8,
+		}
+
+		pub const UNTOUCHED: &str = stringify!(#[configure(unix, #[a])]);
+	}
+}]
+mod idempotent {
+	pub struct Idempotent {
+		#[configure(
+			!unix,
+			#[a]
+			#[configure(feature = "x", /// Docs.
+			)]
+			else #[b]
+		)]
+		#[configure_if(windows => #[c])]
+		pub field: u8,
+	}
+
+	pub const UNTOUCHED: &str = stringify!(#[configure(unix, #[a])]);
+}
+
+// Items without any helper attributes are emitted exactly as they were written.
+#[cfg_attrs]
+#[expands_to {
+	/// Docs.
+	#[derive(Clone)]
+	pub(crate) struct Untouched<T: Copy = u8>(T, #[allow(unused)] u8)
+	where
+		T: Default;
+}]
+/// Docs.
+#[derive(Clone)]
+pub(crate) struct Untouched<T: Copy = u8>(T, #[allow(unused)] u8)
+where
+	T: Default;
+
+struct Unconfigured;
+
+#[cfg_attrs]
+impl Unconfigured {
+	fn kept() -> u8 {
+		1
+	}
+}
+
+#[test]
+fn unconfigured_items_are_kept() {
+	assert_eq!(Unconfigured::kept(), 1);
+}
+
+// All the attributes of one `configure` attribute share a single `cfg_attr`, even when some of them
+// are nested `configure` attributes.
+#[cfg_attrs]
+#[expands_to {
+	struct Grouped {
+		#[cfg_attr(
+			all(unix, feature = "x", not(debug_assertions)),
+			doc = " Docs.",
+			a,
+			cfg_attr(windows, b),
+			cfg_attr(c, d),
+			cfg_attr(all(not(any(c)), e), f),
+			g
+		)]
+		field: u8,
+	}
+}]
+struct Grouped {
+	#[configure(
+		all(unix, feature = "x", not(debug_assertions)),
+		/// Docs.
+		#[a]
+		#[configure(windows, #[b])]
+		#[configure_if(c => #[d]; e => #[f])]
+		#[g]
+	)]
+	field: u8,
+}
+
+#[cfg_attrs]
+#[processed]
+#[configure(all(), #[derive(Clone, Debug, PartialEq)])]
+struct BeforeOtherMacros {
+	#[configure(unix, #[allow(unused)])]
+	field: u8,
+}
+
+#[test]
+fn other_attribute_macros_see_processed_attributes() {
+	let value = BeforeOtherMacros { field: 1 };
+	assert_eq!(value.clone(), value);
+}
+
+// Configured doc comments are emitted exactly where they were written among the unconditional
+// ones, even when other configured attributes around them are sorted, so rustdoc reads them in
+// order.
+#[cfg_attrs(sort)]
+#[derive(DocOrder, HelperOrder)]
+/// First.
+#[configure(
+	all(),
+	/// Second.
+)]
+#[configure(all(), #[helper(z)])]
+#[configure(all(), #[helper(a)])]
+/// Third.
+#[configure(
+	any(),
+	/// Never.
+	else
+	/// Fourth.
+)]
+#[configure_if(
+	any() => /// Never.
+	;
+	_ => /// Fifth.
+)]
+/// Sixth.
+struct InterleavedDocs;
+
+#[test]
+fn configured_docs_keep_their_position() {
+	assert_eq!(
+		InterleavedDocs::DOCS,
+		[" First.", " Second.", " Third.", " Fourth.", " Fifth.", " Sixth."],
+	);
+	assert_eq!(InterleavedDocs::HELPERS, ["a", "z"]);
+}
+
+// Whichever form a configured attribute takes, and even when it is sorted, it is rewritten to a
+// `cfg_attr` whose condition comes first.
+#[cfg_attrs(sort, mixed_site)]
+#[expands_to {
+	struct ConditionFirst {
+		#[cfg_attr(feature = "a", cfg_attr(not(b), c))]
+		#[cfg_attr(feature = "y", d)]
+		#[cfg_attr(feature = "z", e)]
+		#[cfg_attr(not(feature = "z"), f)]
+		#[cfg_attr(not(x), g)]
+		#[cfg_attr(unix, a)]
+		#[cfg_attr(all(not(any(unix)), windows), b)]
+		field: u8,
+	}
+}]
+struct ConditionFirst {
+	#[configure(!x, #[g])]
+	#[configure(feature = "z", #[e] else #[f])]
+	#[configure_if(unix => #[a]; windows => #[b])]
+	#[configure_each(feature = "y" => #[d])]
+	#[configure(feature = "a", #[configure(!b, #[c])])]
+	field: u8,
+}
@@ -0,0 +1,130 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#[path = "../build/readme.rs"]
+mod readme;
+
+/// Generates the `README.md` for the given `docs`, returning only what follows the header.
+fn generate(docs: &str, indentation_width: usize) -> String {
+	let (readme, warnings) = readme::generate(&format!("{}\n\n{docs}", readme::COPYRIGHT), indentation_width);
+	assert!(warnings.is_empty(), "unexpected warnings for {docs:?}");
+	let (_, body) = readme.split_once("# `#[cfg_attrs { ... }]`\n").unwrap();
+
+	body.to_owned()
+}
+
+const INDENTED: &str = "\
+- Item:
+
+  ```
+  two();
+  ```
+
+   ```
+   three();
+   ```
+
+    ```
+    four();
+    ```
+";
+
+#[test]
+fn indentation_width_two() {
+	assert_eq!(
+		generate(INDENTED, 2),
+		"\
+- Item:
+
+  ```rust
+  two();
+  ```
+
+   ```
+   three();
+   ```
+
+    ```
+    four();
+    ```
+",
+	);
+}
+
+#[test]
+fn indentation_width_four() {
+	assert_eq!(
+		generate(INDENTED, 4),
+		"\
+- Item:
+
+  ```rust
+  two();
+  ```
+
+   ```rust
+   three();
+   ```
+
+    ```rust
+    four();
+    ```
+",
+	);
+}
+
+#[test]
+fn headings_are_demoted() {
+	assert_eq!(
+		generate("# Heading\n#Not a heading\n", 3),
+		"## Heading\n#Not a heading\n"
+	);
+}
+
+#[test]
+fn hidden_lines_are_removed() {
+	assert_eq!(
+		generate("```\n# use hidden;\n#\nshown();\n## escaped();\n##\n```\n", 3),
+		"```rust\nshown();\n# escaped();\n#\n```\n",
+	);
+	assert_eq!(
+		generate("- Item:\n\n  ```\n  # use hidden;\n  shown();\n  ```\n", 3),
+		"- Item:\n\n  ```rust\n  shown();\n  ```\n",
+	);
+}
+
+#[test]
+fn hidden_code_blocks_are_omitted() {
+	assert_eq!(
+		generate("Before.\n```\n# use hidden;\n#\n```\nAfter.\n", 3),
+		"Before.\nAfter.\n"
+	);
+	assert_eq!(generate("```\n```\n", 3), "```rust\n```\n");
+	assert_eq!(generate("```\n## escaped();\n```\n", 3), "```rust\n# escaped();\n```\n");
+}
+
+#[test]
+fn unterminated_code_block_is_closed() {
+	let docs = format!("{}\n\nText.\n\n```\n# use hidden;\nunclosed();\n", readme::COPYRIGHT);
+	let (readme, warnings) = readme::generate(&docs, 3);
+
+	assert!(readme.ends_with("Text.\n\n```rust\nunclosed();\n```\n"), "{readme:?}");
+	// The copyright notice takes the first three lines, followed by a blank line.
+	assert_eq!(
+		warnings
+			.iter()
+			.map(|warning| (warning.line, warning.message))
+			.collect::<Vec<_>>(),
+		[(7, "unterminated code block, closing it at the end of the file")],
+	);
+}
+
+#[test]
+fn crlf_line_endings_are_normalized() {
+	let docs = "# Heading\r\nText.\r\n```\r\n# use hidden;\r\nshown();\r\n```\r\nAfter.\r\n";
+	let readme = generate(docs, 3);
+
+	assert_eq!(readme, "## Heading\nText.\n```rust\nshown();\n```\nAfter.\n");
+	assert!(!readme.contains('\r'));
+}
@@ -0,0 +1,72 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+// `rustc` expands `#[cfg_attr(...)]` on the item itself before `#[expands_to]` sees it, so these use
+// conditions that always hold.
+
+#[cfg_attrs]
+#[expands_to {
+	#[used]
+	#[link_section = ".data"]
+	static mut COUNTER: u32 = 0;
+}]
+#[configure(all(), #[used] #[link_section = ".data"])]
+static mut COUNTER: u32 = 0;
+
+#[cfg_attrs]
+#[expands_to {
+	pub static mut BUFFER: [u8; 4] = [0; 4];
+}]
+#[configure(any(), #[used])]
+pub static mut BUFFER: [u8; 4] = [0; 4];
+
+#[cfg_attrs]
+#[configure(all(), #[used])]
+static mut CONFIGURED: u32 = 0;
+
+#[test]
+fn static_mut_is_mutable() {
+	// SAFETY: this test is the only code that accesses `CONFIGURED`.
+	let value = unsafe {
+		CONFIGURED += 1;
+		CONFIGURED
+	};
+
+	assert_eq!(value, 1);
+}
+
+// These are within a module so that their `cfg_attr` attributes are seen unexpanded.
+#[cfg_attrs]
+#[expands_to {
+	mod ffi {
+		#[cfg_attr(target_arch = "arm", link_section = ".fast", no_mangle)]
+		pub static FAST: u32 = 0;
+
+		#[cfg_attr(target_arch = "arm", unsafe(link_section = ".fast"), unsafe(no_mangle))]
+		#[cfg_attr(not(target_arch = "arm"), used)]
+		pub static UNSAFE_FORM: u32 = 0;
+	}
+}]
+mod ffi {
+	#[configure(target_arch = "arm", #[link_section = ".fast"], #[no_mangle])]
+	pub static FAST: u32 = 0;
+
+	#[configure(target_arch = "arm", #[unsafe(link_section = ".fast")] #[unsafe(no_mangle)] else #[used])]
+	pub static UNSAFE_FORM: u32 = 0;
+}
+
+#[cfg_attrs]
+#[configure(all(), #[unsafe(no_mangle)])]
+#[configure(any(), #[unsafe(link_section = ".never")])]
+pub static CFG_ATTRS_EXPORTED: u32 = 1;
+
+#[test]
+fn unsafe_attributes_are_configured() {
+	assert_eq!(CFG_ATTRS_EXPORTED, 1);
+}
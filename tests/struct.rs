@@ -0,0 +1,265 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	struct Named<T>
+	where
+		T: Clone,
+	{
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		x: T,
+	}
+}]
+struct Named<T>
+where
+	T: Clone,
+{
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	x: T,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct Tuple<T>(
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		T,
+	)
+	where
+		T: Clone;
+}]
+struct Tuple<T>(
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	T,
+)
+where
+	T: Clone;
+
+#[cfg_attrs]
+#[expands_to {
+	struct NamedMarker<T> {
+		value: T,
+		#[cfg_attr(feature = "x", cfg(feature = "x"))]
+		_marker: PhantomData<T>,
+	}
+}]
+struct NamedMarker<T> {
+	value: T,
+	#[configure(feature = "x", #[cfg(feature = "x")])]
+	_marker: PhantomData<T>,
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct TupleMarker<T>(
+		T,
+		#[cfg_attr(feature = "x", cfg(feature = "x"))]
+		PhantomData<T>
+	);
+}]
+struct TupleMarker<T>(T, #[configure(feature = "x", #[cfg(feature = "x")])] PhantomData<T>);
+
+mod marker {
+	use std::marker::PhantomData;
+
+	use cfg_attrs::cfg_attrs;
+
+	#[cfg_attrs]
+	pub struct NamedPresent<T> {
+		pub value: T,
+		#[configure(all(), #[cfg(all())])]
+		pub marker: PhantomData<T>,
+	}
+
+	#[cfg_attrs]
+	pub struct NamedAbsent<T> {
+		pub value: T,
+		#[configure(all(), #[cfg(any())])]
+		pub marker: PhantomData<T>,
+	}
+
+	#[cfg_attrs]
+	pub struct TuplePresent<T>(pub T, #[configure(all(), #[cfg(all())])] pub PhantomData<T>);
+
+	#[cfg_attrs]
+	pub struct TupleAbsent<T>(pub T, #[configure(all(), #[cfg(any())])] pub PhantomData<T>);
+}
+
+#[test]
+fn gated_marker_fields() {
+	use std::marker::PhantomData;
+
+	use marker::*;
+
+	let present = NamedPresent {
+		value: 1,
+		marker: PhantomData,
+	};
+	let absent = NamedAbsent { value: 2 };
+	assert_eq!((present.value, present.marker, absent.value), (1, PhantomData, 2));
+
+	let present = TuplePresent(1, PhantomData);
+	let absent = TupleAbsent(2);
+	assert_eq!((present.0, present.1, absent.0), (1, PhantomData, 2));
+}
+
+#[cfg_attrs]
+#[expands_to {
+	struct GenericDefaults<'a, T: Copy = u8, const N: usize = 4>
+	where
+		T: 'a,
+	{
+		#[cfg_attr(feature = "x", doc = " Docs.")]
+		field: &'a [T; N],
+	}
+}]
+struct GenericDefaults<'a, T: Copy = u8, const N: usize = 4>
+where
+	T: 'a,
+{
+	#[configure(feature = "x", /// Docs.
+	)]
+	field: &'a [T; N],
+}
+
+// `configure_field` and modules require the `full` feature.
+#[cfg(feature = "full")]
+#[cfg_attrs]
+#[expands_to {
+	struct ConfiguredFields {
+		a: u8,
+		#[cfg(feature = "x")]
+		b: u32,
+		#[cfg(feature = "x")]
+		#[cfg_attr(unix, doc = " Docs.")]
+		pub c: u32,
+		#[cfg(not(feature = "y"))]
+		d: u64,
+	}
+}]
+#[configure_field(feature = "x", b: u32, #[configure(unix, /// Docs.
+)] pub c: u32)]
+#[configure_field(!feature = "y", d: u64,)]
+struct ConfiguredFields {
+	a: u8,
+}
+
+#[cfg(feature = "full")]
+#[cfg_attrs]
+#[expands_to {
+	struct ConfiguredTupleFields(u8, #[cfg(feature = "x")] u32);
+}]
+#[configure_field(feature = "x", u32)]
+struct ConfiguredTupleFields(u8);
+
+#[cfg(feature = "full")]
+#[cfg_attrs]
+#[configure_field(all(), enabled: u8)]
+#[configure_field(any(), disabled: u8)]
+struct GatedFields {
+	kept: u8,
+}
+
+#[cfg(feature = "full")]
+#[test]
+fn configured_fields_are_gated() {
+	let GatedFields { kept, enabled } = GatedFields { kept: 0, enabled: 1 };
+	assert_eq!((kept, enabled), (0, 1));
+}
+
+// The error for the malformed field can't be emitted within the struct, so it is emitted before it
+// instead. This is within a module so that `#[expands_to]` sees it.
+#[cfg(feature = "full")]
+#[cfg_attrs]
+#[expands_to {
+	mod malformed_field {
+		::core::compile_error! {
+			"expected `,` after the condition: `configure(<condition>, <attributes>)` (a trailing comma with no attributes, `configure(<condition>,)`, configures nothing)"
+		}
+		struct Fields {
+			#[cfg_attr(unix, allow(unused))]
+			before: u8,
+			malformed: u8,
+			#[cfg_attr(unix, allow(unused))]
+			after: u8,
+		}
+	}
+}]
+mod malformed_field {
+	struct Fields {
+		#[configure(unix, #[allow(unused)])]
+		before: u8,
+		#[configure(unix)]
+		malformed: u8,
+		#[configure(unix, #[allow(unused)])]
+		after: u8,
+	}
+}
+
+// Options make `#[cfg_attrs]` reconstruct the item even without any helper attributes, so empty
+// structs are checked both with and without configured attributes.
+#[cfg(feature = "full")]
+#[cfg_attrs(sort)]
+#[expands_to {
+	mod empty {
+		struct Named {}
+		struct Tuple();
+		struct Unit;
+		struct Generic<T>
+		where
+			T: Copy, {}
+		#[cfg_attr(feature = "x", derive(Debug))]
+		pub struct Configured {}
+	}
+}]
+mod empty {
+	struct Named {}
+	struct Tuple();
+	struct Unit;
+	struct Generic<T>
+	where
+		T: Copy, {}
+	#[configure(feature = "x", #[derive(Debug)])]
+	pub struct Configured {}
+}
+
+#[cfg_attrs(sort)]
+#[derive(Debug, Default, PartialEq)]
+struct EmptyNamed {}
+
+#[test]
+fn empty_structs_are_kept() {
+	assert_eq!(EmptyNamed::default(), EmptyNamed {});
+}
+
+// Without the `full` feature, structs are parsed as a `DeriveInput` instead, so this is also run
+// with `--no-default-features` to check that path.
+#[cfg_attrs]
+#[derive(Debug)]
+#[configure(all(), #[derive(Clone, PartialEq)])]
+#[configure(any(), #[derive(Default)])]
+struct Derived<T: Copy = u8>
+where
+	T: Default,
+{
+	#[configure(all(), #[allow(unused)])]
+	value: T,
+	#[configure(any(), #[cfg(any())])]
+	kept: [T; 2],
+}
+
+#[test]
+fn derive_input_is_configured() {
+	let derived = Derived { value: 1, kept: [2, 3] };
+	assert_eq!(derived.clone(), derived);
+}
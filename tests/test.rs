@@ -0,0 +1,53 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use std::path::Path;
+use std::process::Command;
+
+use cfg_attrs::cfg_attrs;
+
+// If this weren't made a test, it would be warned about as dead code (which is denied when running
+// clippy), since the `else` clause doesn't apply.
+#[cfg_attrs]
+#[configure(all(), #[test] else #[allow(dead_code)])]
+fn enabled() {
+	assert_eq!(1 + 1, 2);
+}
+
+// If this were made a test, it would fail. Without the `else` clause, it would be warned about as
+// dead code.
+#[cfg_attrs]
+#[configure(any(), #[test] else #[allow(dead_code)])]
+fn disabled() {
+	panic!("`disabled` should not be a test");
+}
+
+/// Lists the tests and benchmarks of the `tests/harness` crate, built with the given `features`.
+fn harness_tests(features: &str) -> Vec<String> {
+	let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+	let output = Command::new(env!("CARGO"))
+		.args(["test", "--lib", "--features", features, "--manifest-path"])
+		.arg(manifest.join("tests/harness/Cargo.toml"))
+		.args(["--", "--list"])
+		// The outer `cargo test` holds the lock on its own target directory.
+		.env("CARGO_TARGET_DIR", manifest.join("target/harness"))
+		.output()
+		.expect("failed to run `cargo test` for `tests/harness`");
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+	String::from_utf8(output.stdout)
+		.unwrap()
+		.lines()
+		.filter(|line| line.ends_with(": test") || line.ends_with(": bench"))
+		.map(str::to_owned)
+		.collect()
+}
+
+#[test]
+fn feature_gated_tests_are_only_listed_with_the_feature() {
+	assert_eq!(harness_tests(""), ["always: test"]);
+	assert_eq!(harness_tests("slow-tests"), ["always: test", "slow: test"]);
+}
@@ -0,0 +1,58 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	trait Lending {
+		#[cfg_attr(feature = "x", doc = "A lent item.")]
+		type Item<'a>: 'a
+		where
+			Self: 'a;
+
+		#[cfg_attr(feature = "x", doc = "A lent item with a default.")]
+		type WithDefault<'a, T: Clone + 'a>: Iterator<Item = &'a T> = std::slice::Iter<'a, T>
+		where
+			Self: 'a;
+	}
+}]
+trait Lending {
+	#[configure(feature = "x", #[doc = "A lent item."])]
+	type Item<'a>: 'a
+	where
+		Self: 'a;
+
+	#[configure(feature = "x", #[doc = "A lent item with a default."])]
+	type WithDefault<'a, T: Clone + 'a>: Iterator<Item = &'a T>
+		= std::slice::Iter<'a, T>
+	where
+		Self: 'a;
+}
+
+#[cfg_attrs]
+trait Gat {
+	#[configure(all(), #[doc = "A lent item."])]
+	type Item<'a>: 'a
+	where
+		Self: 'a;
+
+	fn lend(&self) -> Self::Item<'_>;
+}
+
+impl Gat for Vec<u8> {
+	type Item<'a> = &'a [u8];
+
+	fn lend(&self) -> Self::Item<'_> {
+		self
+	}
+}
+
+#[test]
+fn gat_round_trips() {
+	assert_eq!(vec![1, 2].lend(), &[1, 2]);
+}
@@ -0,0 +1,37 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	#[allow(type_alias_bounds)]
+	type Pairs<'a, T: Clone + 'a, const N: usize = 2>
+	where
+		T: Default,
+	= [(&'a T, T); N];
+}]
+#[configure(all(), #[allow(type_alias_bounds)])]
+type Pairs<'a, T: Clone + 'a, const N: usize = 2>
+where
+	T: Default,
+= [(&'a T, T); N];
+
+// Without the configured `#[allow(type_alias_bounds)]`, this would emit a warning (which is denied
+// when running clippy).
+#[cfg_attrs]
+#[configure(all(), #[allow(type_alias_bounds)])]
+type Items<T: Clone>
+where
+	T: Default,
+= Vec<T>;
+
+#[test]
+fn where_clause_is_kept_before_the_type() {
+	let items: Items<u8> = vec![1];
+	assert_eq!(items, [1]);
+}
@@ -0,0 +1,57 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::mem::ManuallyDrop;
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	union Bytes<T: Copy>
+	where
+		T: Default,
+	{
+		#[cfg_attr(feature = "x", cfg(feature = "x"))]
+		vec: ManuallyDrop<Vec<u8>>,
+		#[cfg_attr(feature = "x", doc = " Only documented with `x`.")]
+		value: T,
+		int: u64,
+	}
+}]
+union Bytes<T: Copy>
+where
+	T: Default,
+{
+	#[configure(feature = "x", #[cfg(feature = "x")])]
+	vec: ManuallyDrop<Vec<u8>>,
+	#[configure(
+		feature = "x",
+		/// Only documented with `x`.
+	)]
+	value: T,
+	int: u64,
+}
+
+// A configured `#[cfg(...)]` attribute only removes the field if the `configure` condition holds and
+// the `cfg` condition doesn't.
+#[cfg_attrs]
+#[configure(all(), #[derive(Clone, Copy)])]
+union Gated {
+	#[configure(all(), #[cfg(any())])]
+	removed: ManuallyDrop<Vec<u8>>,
+	#[configure(any(), #[cfg(any())])]
+	kept: ManuallyDrop<[u8; 8]>,
+	int: u64,
+}
+
+#[test]
+fn gated_union_fields_are_removed() {
+	let gated = Gated { int: 1 };
+	let copy = gated;
+
+	// SAFETY: `kept` and `int` are both plain bytes of the same size.
+	assert_eq!(unsafe { copy.int }, 1);
+	assert_eq!(unsafe { *copy.kept }, 1u64.to_ne_bytes());
+}
@@ -0,0 +1,87 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+// `rustc` expands `#[cfg_attr(...)]` on the item itself before `#[expands_to]` sees it, so these use
+// conditions that always hold.
+
+#[cfg_attrs]
+#[expands_to {
+	#[macro_use]
+	use foo::*;
+}]
+#[configure(all(), #[macro_use])]
+use foo::*;
+
+#[cfg_attrs]
+#[expands_to {
+	#[doc = " Docs."]
+	use ::foo::bar;
+}]
+#[configure(all(), /// Docs.
+)]
+use ::foo::bar;
+
+#[cfg_attrs]
+#[expands_to {
+	pub use ::foo::{bar::*, baz as _};
+}]
+#[configure(any(), #[doc(hidden)])]
+pub use ::foo::{bar::*, baz as _};
+
+mod prelude {
+	pub const PRELUDE: u8 = 1;
+}
+
+#[cfg_attrs]
+#[configure(all(), #[allow(unused_imports)])]
+use self::prelude::*;
+
+#[test]
+fn glob_import_is_kept() {
+	assert_eq!(PRELUDE, 1);
+}
+
+// The re-exports are within a module so that their `cfg_attr` attributes are seen unexpanded.
+#[cfg_attrs]
+#[expands_to {
+	mod gated {
+		#[cfg_attr(feature = "x", doc(hidden), cfg(feature = "x"))]
+		pub use ::foo::Bar;
+
+		#[cfg(feature = "x")]
+		#[cfg_attr(feature = "y", doc(hidden))]
+		pub use ::foo::Baz;
+	}
+}]
+mod gated {
+	#[configure(feature = "x", #[doc(hidden)] #[cfg(feature = "x")])]
+	pub use ::foo::Bar;
+
+	#[cfg(feature = "x")]
+	#[configure(feature = "y", #[doc(hidden)])]
+	pub use ::foo::Baz;
+}
+
+// If the re-export weren't removed, it would conflict with the constant of the same name.
+#[cfg_attrs]
+#[cfg(any())]
+#[configure(all(), #[doc(hidden)])]
+pub use self::prelude::PRELUDE as GATED;
+
+pub const GATED: u8 = 2;
+
+#[cfg_attrs]
+#[configure(all(), #[doc(hidden)] #[cfg(all())])]
+pub use self::prelude::PRELUDE as HIDDEN;
+
+#[test]
+fn gated_re_exports_compose() {
+	assert_eq!(GATED, 2);
+	assert_eq!(HIDDEN, 1);
+}
@@ -0,0 +1,214 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+#![cfg(feature = "full")]
+
+use cfg_attrs::cfg_attrs;
+use cfg_attrs_test_macros::expands_to;
+
+#[cfg_attrs]
+#[expands_to {
+	trait Methods {
+		#[cfg(feature = "x")]
+		fn foo(&self)
+		where
+			Self: Clone;
+		#[cfg(not(feature = "x"))]
+		fn foo(&self);
+
+		#[cfg(unix)]
+		#[cfg(feature = "x")]
+		fn bar<T>(&self, value: T)
+		where
+			T: Copy,
+			T: Clone,
+			Self: Sized;
+		#[cfg(unix)]
+		#[cfg(not(feature = "x"))]
+		fn bar<T>(&self, value: T)
+		where
+			T: Copy,
+			T: Clone;
+		#[cfg(not(unix))]
+		#[cfg(feature = "x")]
+		fn bar<T>(&self, value: T)
+		where
+			T: Copy,
+			Self: Sized;
+		#[cfg(not(unix))]
+		#[cfg(not(feature = "x"))]
+		fn bar<T>(&self, value: T)
+		where
+			T: Copy;
+	}
+}]
+trait Methods {
+	#[configure_where(feature = "x", Self: Clone)]
+	fn foo(&self);
+
+	#[configure_where(unix, T: Clone)]
+	#[configure_where(feature = "x", Self: Sized)]
+	fn bar<T>(&self, value: T)
+	where
+		T: Copy;
+}
+
+#[cfg_attrs]
+#[configure_where(all(), T: Clone)]
+fn duplicate<T>(value: T) -> (T, T) {
+	(value.clone(), value)
+}
+
+#[cfg_attrs]
+#[configure_where(any(), T: Clone)]
+fn single<T>(value: T) -> T {
+	value
+}
+
+#[test]
+fn predicates_are_configured() {
+	assert_eq!(duplicate(1), (1, 1));
+	assert_eq!(single(1), 1);
+}
+
+#[cfg_attrs]
+#[expands_to {
+	trait Callbacks {
+		#[cfg(feature = "x")]
+		fn filter<F, G>(&self, predicate: F, map: G)
+		where
+			G: FnMut(u8) -> (u8, u8),
+			for<'a> F: Fn(&'a str) -> bool,
+			F: for<'a, 'b> Fn(&'a str, &'b str) -> Option<&'a str> + Send;
+		#[cfg(not(feature = "x"))]
+		fn filter<F, G>(&self, predicate: F, map: G)
+		where
+			G: FnMut(u8) -> (u8, u8);
+	}
+}]
+trait Callbacks {
+	#[configure_where(
+		feature = "x",
+		for<'a> F: Fn(&'a str) -> bool,
+		F: for<'a, 'b> Fn(&'a str, &'b str) -> Option<&'a str> + Send,
+	)]
+	fn filter<F, G>(&self, predicate: F, map: G)
+	where
+		G: FnMut(u8) -> (u8, u8);
+}
+
+#[cfg_attrs]
+#[configure_where(all(), for<'a> F: Fn(&'a str) -> bool)]
+fn call<F>(callback: F) -> bool
+where
+	F: Fn(&str) -> bool,
+{
+	callback("call")
+}
+
+#[test]
+fn higher_ranked_predicates_are_configured() {
+	assert!(call(|value| value == "call"));
+}
+
+// Constants can't have generics on stable, so they're emitted without a where clause, and
+// `configure_where` on them is an error rather than silently dropping its predicates.
+#[cfg_attrs]
+#[expands_to {
+	trait Constants {
+		::core::compile_error! { "`configure_where` can only be used on items with generics" }
+		#[cfg_attr(feature = "x", allow(unused))]
+		const A: u8;
+		const B: u8 = 0;
+	}
+}]
+trait Constants {
+	#[configure_where(feature = "x", Self: Sized)]
+	#[configure(feature = "x", #[allow(unused)])]
+	const A: u8;
+	const B: u8 = 0;
+}
+
+#[cfg_attrs]
+#[expands_to {
+	mod constants {
+		#[cfg_attr(feature = "x", allow(unused))]
+		pub const C: u8 = 0;
+	}
+}]
+mod constants {
+	#[configure(feature = "x", #[allow(unused)])]
+	pub const C: u8 = 0;
+}
+
+// A trait's own where clause can be configured too, even alongside its supertraits and the where
+// clauses of its items.
+#[cfg_attrs]
+#[expands_to {
+	mod traits {
+		#[cfg(feature = "x")]
+		pub trait Bounded: Clone
+		where
+			Self: Sized,
+			Self: Send
+		{
+			#[cfg_attr(feature = "x", allow(unused))]
+			fn method(&self)
+			where
+				Self: Sync;
+		}
+		#[cfg(not(feature = "x"))]
+		pub trait Bounded: Clone
+		where
+			Self: Sized,
+		{
+			#[cfg_attr(feature = "x", allow(unused))]
+			fn method(&self)
+			where
+				Self: Sync;
+		}
+
+		#[cfg(unix)]
+		pub trait Unbounded
+		where
+			Self: Send
+		{}
+		#[cfg(not(unix))]
+		pub trait Unbounded {}
+	}
+}]
+mod traits {
+	#[configure_where(feature = "x", Self: Send)]
+	pub trait Bounded: Clone
+	where
+		Self: Sized,
+	{
+		#[configure(feature = "x", #[allow(unused)])]
+		fn method(&self)
+		where
+			Self: Sync;
+	}
+
+	#[configure_where(unix, Self: Send)]
+	pub trait Unbounded {}
+}
+
+#[cfg_attrs]
+#[configure_where(all(), Self: Copy)]
+trait Duplicable {
+	fn duplicate(self) -> (Self, Self)
+	where
+		Self: Sized,
+	{
+		// `Self: Copy` is only known because of the configured predicate.
+		(self, self)
+	}
+}
+
+impl Duplicable for u8 {}
+
+#[test]
+fn trait_where_clause_is_configured() {
+	assert_eq!(1.duplicate(), (1, 1));
+}